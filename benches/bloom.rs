@@ -0,0 +1,223 @@
+#[macro_use]
+extern crate criterion;
+extern crate bloom_filter_rs as bloom_filter;
+
+use bloom_filter::{BlockedBloomFilter, BloomFilter, BloomFilterK, BloomHasher, Fnv1a, Murmur3};
+use criterion::{black_box, Criterion};
+
+#[cfg(feature = "xxhash")]
+use bloom_filter::XxHash;
+
+/// Generate `n` distinct keys of `len` bytes.
+fn keys(n: u32, len: usize) -> Vec<Vec<u8>> {
+    (0..n)
+        .map(|i| i.to_le_bytes().iter().cycle().cloned().take(len).collect())
+        .collect()
+}
+
+fn double_hashing(c: &mut Criterion) {
+    let keys = keys(1000, 64);
+    let mut bloom_filter = BloomFilter::new(Murmur3, 7, 9586);
+    bloom_filter.insert_all(&keys);
+
+    let mut group = c.benchmark_group("k7_64_byte_keys");
+    group.bench_function("contains", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box(bloom_filter.contains(key));
+            }
+        })
+    });
+    // The cost of hashing every key once per hash function, as was done before
+    // double hashing.
+    group.bench_function("hash_k_times", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                for seed in 0..7 {
+                    black_box(Murmur3.hash(seed, key));
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+fn blocked_lookup(c: &mut Criterion) {
+    let keys = keys(1_000_000, 8);
+    let queries = &keys[..10000];
+
+    let mut bloom_filter = BloomFilter::optimal(Murmur3, 1_000_000, 0.01);
+    bloom_filter.insert_all(&keys);
+    let mut blocked_filter = BlockedBloomFilter::optimal(Murmur3, 1_000_000, 0.01);
+    blocked_filter.insert_all(&keys);
+
+    let mut group = c.benchmark_group("lookup_1m");
+    group.bench_function("standard", |b| {
+        b.iter(|| {
+            for key in queries.iter() {
+                black_box(bloom_filter.contains(key));
+            }
+        })
+    });
+    group.bench_function("blocked", |b| {
+        b.iter(|| {
+            for key in queries.iter() {
+                black_box(blocked_filter.contains(key));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn blocked_probe(c: &mut Criterion) {
+    let keys = keys(100_000, 8);
+    let queries = &keys[..10000];
+
+    let mut blocked_filter = BlockedBloomFilter::new(Fnv1a, 16, 4096);
+    blocked_filter.insert_all(&keys);
+
+    // Compare runs with and without the `simd` feature, the cheap hash leaves
+    // testing the 16 bits of each query as the dominant cost.
+    let mut group = c.benchmark_group("blocked_k16_probe");
+    let name = if cfg!(feature = "simd") { "simd" } else { "scalar" };
+    group.bench_function(name, |b| {
+        b.iter(|| {
+            for key in queries.iter() {
+                black_box(blocked_filter.contains(key));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn pow2_lookup(c: &mut Criterion) {
+    let keys = keys(10000, 8);
+
+    // Both filters fit in cache, so that only the index computation differs.
+    // The number of hash functions is fixed, since `optimal_pow2` picks a
+    // larger one for the larger array.
+    let mut bloom_filter = BloomFilter::new(Fnv1a, 7, 95851);
+    bloom_filter.insert_all(&keys);
+    let mut pow2_filter = BloomFilter::new(Fnv1a, 7, 131072);
+    pow2_filter.insert_all(&keys);
+
+    let mut group = c.benchmark_group("pow2_k7_lookup");
+    group.bench_function("multiply", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box(bloom_filter.contains(key));
+            }
+        })
+    });
+    group.bench_function("shift", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box(pow2_filter.contains(key));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn fnv1a_lookup(c: &mut Criterion) {
+    let keys = keys(1000, 8);
+
+    let mut murmur3_filter = BloomFilter::new(Murmur3, 7, 9586);
+    murmur3_filter.insert_all(&keys);
+    let mut fnv1a_filter = BloomFilter::new(Fnv1a, 7, 9586);
+    fnv1a_filter.insert_all(&keys);
+
+    let mut group = c.benchmark_group("k7_8_byte_keys");
+    group.bench_function("murmur3", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box(murmur3_filter.contains(key));
+            }
+        })
+    });
+    group.bench_function("fnv1a", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box(fnv1a_filter.contains(key));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn const_k_lookup(c: &mut Criterion) {
+    let keys = keys(1_000_000, 8);
+    let queries = &keys[..10000];
+
+    let mut runtime_filter = BloomFilter::new(Fnv1a, 8, 9_585_059);
+    runtime_filter.insert_all(&keys);
+    let mut const_filter = BloomFilterK::<8, _>::new(Fnv1a, 9_585_059);
+    const_filter.insert_all(&keys);
+
+    let mut group = c.benchmark_group("k8_lookup_1m");
+    group.bench_function("runtime_k", |b| {
+        b.iter(|| {
+            for key in queries.iter() {
+                black_box(runtime_filter.contains(key));
+            }
+        })
+    });
+    group.bench_function("const_k", |b| {
+        b.iter(|| {
+            for key in queries.iter() {
+                black_box(const_filter.contains(key));
+            }
+        })
+    });
+    group.finish();
+}
+
+#[cfg(feature = "xxhash")]
+fn xxhash_lookup(c: &mut Criterion) {
+    let keys = keys(1000, 128);
+
+    let mut murmur3_filter = BloomFilter::new(Murmur3, 7, 9586);
+    murmur3_filter.insert_all(&keys);
+    let mut xxhash_filter = BloomFilter::new(XxHash::default(), 7, 9586);
+    xxhash_filter.insert_all(&keys);
+
+    let mut group = c.benchmark_group("k7_128_byte_keys");
+    group.bench_function("murmur3", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box(murmur3_filter.contains(key));
+            }
+        })
+    });
+    group.bench_function("xxhash", |b| {
+        b.iter(|| {
+            for key in keys.iter() {
+                black_box(xxhash_filter.contains(key));
+            }
+        })
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "xxhash"))]
+criterion_group!(
+    benches,
+    double_hashing,
+    blocked_lookup,
+    blocked_probe,
+    pow2_lookup,
+    fnv1a_lookup,
+    const_k_lookup
+);
+#[cfg(feature = "xxhash")]
+criterion_group!(
+    benches,
+    double_hashing,
+    blocked_lookup,
+    blocked_probe,
+    pow2_lookup,
+    fnv1a_lookup,
+    const_k_lookup,
+    xxhash_lookup
+);
+criterion_main!(benches);
@@ -0,0 +1,199 @@
+use bloom::{checked_len, optimal_parameters, Probes};
+use error::BloomError;
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+/// AtomicBloomFilter
+///
+/// A bloom filter whose bits are stored in atomic blocks, so it can be
+/// inserted into and queried concurrently through a shared reference, without
+/// any locking.
+///
+/// `BloomFilter` is already `Sync` when its hasher is, so a filter that is no
+/// longer modified can be shared between reader threads directly. This type is
+/// for filters that keep growing while being read.
+///
+/// All operations use `Ordering::Relaxed`. Bits are only ever set, never
+/// cleared, and each bit is set by an atomic `fetch_or`, so no insert can be
+/// lost to a concurrent one. An insert is visible to a concurrent `contains`
+/// once all of its bits are, so a query racing with the insert of the same
+/// element may return either result, but a query that happens after the
+/// insert, for example after joining the inserting thread, always finds it.
+/// Relaxed ordering does not order the bits of a filter with any other memory,
+/// so it must not be used to publish other data.
+pub struct AtomicBloomFilter<T> {
+    hasher: T,
+    k: u32,
+    len: usize,
+    blocks: Vec<AtomicU32>,
+    insert_count: AtomicUsize,
+}
+
+impl<T: BloomHasher> AtomicBloomFilter<T> {
+    /// Create a new `AtomicBloomFilter` given a `hasher`,
+    /// the number of hash functions to use,
+    /// and the size of the underlying bit array.
+    ///
+    /// Both `k` and `array_size` are clamped to a minimum of 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `array_size` does not fit in a `usize`, as on 32-bit targets.
+    pub fn new(hasher: T, k: u32, array_size: u64) -> Self {
+        let len = checked_len(array_size.max(1)).unwrap_or_else(|e| panic!("{}", e));
+        Self {
+            hasher,
+            k: k.max(1),
+            len,
+            blocks: (0..len.div_ceil(32)).map(|_| AtomicU32::new(0)).collect(),
+            insert_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Create an `AtomicBloomFilter` by computing its optimal parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    /// See `try_optimal` for a non-panicking alternative.
+    pub fn optimal(hasher: T, max_elements: u64, error_rate: f64) -> Self {
+        Self::try_optimal(hasher, max_elements, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create an `AtomicBloomFilter` by computing its optimal parameters,
+    /// returning an error if the parameters are invalid.
+    pub fn try_optimal(hasher: T, max_elements: u64, error_rate: f64) -> Result<Self, BloomError> {
+        let (k, m) = optimal_parameters(max_elements, error_rate)?;
+        Ok(Self::new(hasher, k, m))
+    }
+
+    /// Insert a slice of bytes into the `AtomicBloomFilter`.
+    ///
+    /// Returns `true` if at least one bit was flipped by this call, which
+    /// guarantees the element was not present before.
+    pub fn insert<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        let mut new = false;
+        for index in self.probes(bytes.as_ref()) {
+            let mask = 1 << (index % 32);
+            let previous = self.blocks[index / 32].fetch_or(mask, Ordering::Relaxed);
+            new |= previous & mask == 0;
+        }
+        self.insert_count.fetch_add(1, Ordering::Relaxed);
+        new
+    }
+
+    /// Insert a slice of slices of bytes into the `AtomicBloomFilter`.
+    pub fn insert_all<B: AsRef<[u8]>>(&self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the `AtomicBloomFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.probes(bytes.as_ref()).all(|index| {
+            self.blocks[index / 32].load(Ordering::Relaxed) & (1 << (index % 32)) != 0
+        })
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the size of the underlying bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.len as u64
+    }
+
+    /// Returns the number of times `insert` has been called.
+    pub fn len(&self) -> u64 {
+        self.insert_count.load(Ordering::Relaxed) as u64
+    }
+
+    /// Returns `true` if nothing has been inserted into the `AtomicBloomFilter`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the `k` bit indices for `bytes`.
+    fn probes(&self, bytes: &[u8]) -> Probes {
+        Probes::new(&self.hasher, 0, self.k, self.len, bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bloom::BloomFilter;
+    use hash::Murmur3;
+    use std::thread;
+
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn test_sync() {
+        assert_sync::<BloomFilter<Murmur3>>();
+        assert_sync::<AtomicBloomFilter<Murmur3>>();
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let bloom_filter = AtomicBloomFilter::optimal(Murmur3, 1000, 0.01);
+        bloom_filter.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(bloom_filter.contains(word));
+        }
+        assert_eq!(1000, bloom_filter.len());
+    }
+
+    #[test]
+    fn test_same_bits_as_bloom_filter() {
+        let atomic = AtomicBloomFilter::new(Murmur3, 7, 1001);
+        let mut bloom_filter = BloomFilter::new(Murmur3, 7, 1001);
+        for i in 0..100_u32 {
+            assert_eq!(bloom_filter.insert(i.to_le_bytes()), atomic.insert(i.to_le_bytes()));
+        }
+
+        let blocks: Vec<u32> = atomic.blocks.iter().map(|block| block.load(Ordering::Relaxed)).collect();
+        assert_eq!(bloom_filter.as_blocks(), &blocks[..]);
+    }
+
+    #[test]
+    fn test_concurrent_insert_contains() {
+        let bloom_filter = AtomicBloomFilter::optimal(Murmur3, 40000, 0.01);
+
+        thread::scope(|scope| {
+            for t in 0..4_u32 {
+                let bloom_filter = &bloom_filter;
+                scope.spawn(move || {
+                    for i in 0..10000_u32 {
+                        let key = (t * 10000 + i).to_le_bytes();
+                        bloom_filter.insert(key);
+                        assert!(bloom_filter.contains(key));
+                    }
+                });
+            }
+            for _ in 0..2 {
+                let bloom_filter = &bloom_filter;
+                scope.spawn(move || {
+                    for i in 0..40000_u32 {
+                        bloom_filter.contains(i.to_le_bytes());
+                    }
+                });
+            }
+        });
+
+        assert_eq!(40000, bloom_filter.len());
+        for i in 0..40000_u32 {
+            assert!(bloom_filter.contains(i.to_le_bytes()));
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use bloom::{checked_len, fastrange, optimal_parameters};
+use error::BloomError;
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd;
+
+/// The number of bits in a block, the size of a typical cache line.
+const BLOCK_BITS: u64 = 512;
+
+/// A single cache line of bits.
+#[derive(Clone, Copy)]
+#[repr(align(64))]
+struct Block([u64; 8]);
+
+impl Block {
+    #[inline]
+    fn get(&self, bit: u32) -> bool {
+        self.0[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+    }
+
+    #[inline]
+    fn set(&mut self, bit: u32) {
+        self.0[(bit / 64) as usize] |= 1 << (bit % 64);
+    }
+
+    /// Check whether all `k` bits derived from `h1` and `h2` are set.
+    ///
+    /// With the `simd` feature, this tests eight bits at a time using AVX2 if
+    /// the CPU supports it, and falls back to testing them one at a time.
+    #[inline]
+    fn contains(&self, h1: u32, h2: u32, k: u32) -> bool {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                // Safety: AVX2 support was just checked.
+                return unsafe { simd::contains_avx2(self, h1, h2, k) };
+            }
+        }
+
+        self.contains_scalar(h1, h2, k)
+    }
+
+    #[inline]
+    fn contains_scalar(&self, h1: u32, h2: u32, k: u32) -> bool {
+        (0..k).all(|i| self.get(block_bit(h1, h2, i)))
+    }
+}
+
+/// BlockedBloomFilter
+///
+/// A bloom filter whose bit array is partitioned into 512-bit blocks, with all
+/// `k` bits of an element set within a single block.
+///
+/// Each query therefore touches a single cache line, instead of up to `k`
+/// scattered cache lines, at the cost of a slightly higher false positive
+/// rate than a standard `BloomFilter` of the same size.
+#[derive(Clone)]
+pub struct BlockedBloomFilter<T> {
+    hasher: T,
+    k: u32,
+    blocks: Vec<Block>,
+    insert_count: u64,
+}
+
+impl<T: BloomHasher> BlockedBloomFilter<T> {
+    /// Create a new `BlockedBloomFilter` given a `hasher`,
+    /// the number of hash functions to use,
+    /// and the number of 512-bit blocks.
+    ///
+    /// `k` is clamped to `[1, 512]` and `num_blocks` to a minimum of 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_blocks` does not fit in a `usize`, as on 32-bit targets.
+    pub fn new(hasher: T, k: u32, num_blocks: u64) -> Self {
+        let num_blocks = checked_len(num_blocks.max(1)).unwrap_or_else(|e| panic!("{}", e));
+        Self {
+            hasher,
+            k: k.max(1).min(BLOCK_BITS as u32),
+            blocks: vec![Block([0; 8]); num_blocks],
+            insert_count: 0,
+        }
+    }
+
+    /// Create a `BlockedBloomFilter` by computing its optimal parameters.
+    ///
+    /// The optimal array size is rounded up to a whole number of blocks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    /// See `try_optimal` for a non-panicking alternative.
+    pub fn optimal(hasher: T, max_elements: u64, error_rate: f64) -> Self {
+        Self::try_optimal(hasher, max_elements, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a `BlockedBloomFilter` by computing its optimal parameters,
+    /// returning an error if the parameters are invalid.
+    pub fn try_optimal(hasher: T, max_elements: u64, error_rate: f64) -> Result<Self, BloomError> {
+        let (k, m) = optimal_parameters(max_elements, error_rate)?;
+        let num_blocks = m.div_ceil(BLOCK_BITS);
+        checked_len(num_blocks)?;
+
+        Ok(Self::new(hasher, k, num_blocks))
+    }
+
+    /// Insert a slice of bytes into the `BlockedBloomFilter`.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        let (block, h1, h2) = self.locate(bytes.as_ref());
+        let block = &mut self.blocks[block];
+        for i in 0..self.k {
+            block.set(block_bit(h1, h2, i));
+        }
+        self.insert_count += 1;
+    }
+
+    /// Insert a slice of slices of bytes into the `BlockedBloomFilter`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the `BlockedBloomFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        let (block, h1, h2) = self.locate(bytes.as_ref());
+        self.blocks[block].contains(h1, h2, self.k)
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the size of the underlying bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.blocks.len() as u64 * BLOCK_BITS
+    }
+
+    /// Returns the number of times `insert` has been called.
+    pub fn len(&self) -> u64 {
+        self.insert_count
+    }
+
+    /// Returns `true` if nothing has been inserted into the `BlockedBloomFilter`.
+    pub fn is_empty(&self) -> bool {
+        self.insert_count == 0
+    }
+
+    /// Hash `bytes` once, returning the index of its block and the two hashes
+    /// used to derive the bits within the block.
+    #[inline]
+    fn locate(&self, bytes: &[u8]) -> (usize, u32, u32) {
+        let hash = self.hasher.hash64(0, bytes);
+
+        // The block is selected by the high bits, while the bits within the
+        // block are derived from the low bits of each half. An odd step
+        // guarantees distinct bits for each probe.
+        let block = fastrange(hash, self.blocks.len());
+        (block, hash as u32, (hash >> 32) as u32 | 1)
+    }
+}
+
+/// Returns the `i`-th bit within a block.
+#[inline]
+fn block_bit(h1: u32, h2: u32, i: u32) -> u32 {
+    h1.wrapping_add(i.wrapping_mul(h2)) % BLOCK_BITS as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_insert_as_ref() {
+        let mut filter = BlockedBloomFilter::optimal(Murmur3, 100, 0.01);
+        let word = String::from("apple");
+        filter.insert(&word);
+        filter.insert("banana");
+        filter.insert(vec![1_u8, 2, 3]);
+        filter.insert(word);
+
+        assert!(filter.contains("apple"));
+        assert!(filter.contains("banana"));
+        assert!(filter.contains([1_u8, 2, 3]));
+        assert_eq!(4, filter.len());
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let words: Vec<String> = (0..10000).map(|i| format!("word{}", i)).collect();
+
+        let mut filter = BlockedBloomFilter::optimal(Murmur3, 10000, 0.01);
+        filter.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(filter.contains(word));
+        }
+        assert_eq!(10000, filter.len());
+    }
+
+    #[test]
+    fn test_optimal_rounds_to_blocks() {
+        let filter = BlockedBloomFilter::optimal(Murmur3, 10000, 0.01);
+        assert_eq!(7, filter.num_hashes());
+        assert_eq!(95851_u64.div_ceil(512) * 512, filter.capacity_bits());
+    }
+
+    #[test]
+    fn test_bits_in_single_block() {
+        let mut filter = BlockedBloomFilter::new(Murmur3, 7, 16);
+        filter.insert(b"hello");
+
+        let used: Vec<usize> = (0..16).filter(|&i| filter.blocks[i].0 != [0; 8]).collect();
+        assert_eq!(1, used.len());
+
+        let set: u32 = filter.blocks[used[0]].0.iter().map(|w| w.count_ones()).sum();
+        assert_eq!(7, set);
+    }
+
+    #[test]
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    fn test_simd_matches_scalar() {
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+
+        for k in [1, 7, 8, 9, 16, 23, 512].iter() {
+            let mut filter = BlockedBloomFilter::new(Murmur3, *k, 64);
+            for i in 0..1000_u32 {
+                filter.insert(i.to_le_bytes());
+            }
+
+            for i in 0..20000_u32 {
+                let (block, h1, h2) = filter.locate(&i.to_le_bytes());
+                let block = &filter.blocks[block];
+                let simd = unsafe { simd::contains_avx2(block, h1, h2, filter.k) };
+                assert_eq!(block.contains_scalar(h1, h2, filter.k), simd);
+            }
+        }
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let mut filter = BlockedBloomFilter::optimal(Murmur3, 10000, 0.01);
+        for i in 0..10000_u32 {
+            filter.insert(i.to_le_bytes());
+        }
+
+        let false_positives = (10000..110000_u32)
+            .filter(|i| filter.contains(i.to_le_bytes()))
+            .count();
+        assert!((false_positives as f64 / 100000_f64) < 0.02);
+    }
+}
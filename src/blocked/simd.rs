@@ -0,0 +1,43 @@
+use core::arch::x86_64::*;
+
+use super::{Block, BLOCK_BITS};
+
+/// Check whether all `k` bits derived from `h1` and `h2` are set in `block`,
+/// testing eight bits at a time.
+///
+/// The bits are computed as in `block_bit`, then the 32-bit words holding them
+/// are gathered in a single instruction and masked in parallel.
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn contains_avx2(block: &Block, h1: u32, h2: u32, k: u32) -> bool {
+    let words = block.0.as_ptr() as *const i32;
+
+    let lanes = _mm256_setr_epi32(0, 1, 2, 3, 4, 5, 6, 7);
+    let h1 = _mm256_set1_epi32(h1 as i32);
+    let h2 = _mm256_set1_epi32(h2 as i32);
+    let bit_mask = _mm256_set1_epi32(BLOCK_BITS as i32 - 1);
+    let shift_mask = _mm256_set1_epi32(31);
+    let ones = _mm256_set1_epi32(1);
+
+    let mut i = 0;
+    while i < k {
+        let index = _mm256_add_epi32(_mm256_set1_epi32(i as i32), lanes);
+        let bits = _mm256_and_si256(_mm256_add_epi32(h1, _mm256_mullo_epi32(index, h2)), bit_mask);
+
+        let word = _mm256_i32gather_epi32::<4>(words, _mm256_srli_epi32::<5>(bits));
+        let mask = _mm256_sllv_epi32(ones, _mm256_and_si256(bits, shift_mask));
+        let hit = _mm256_cmpeq_epi32(_mm256_and_si256(word, mask), mask);
+
+        // Lanes past `k` in the final group are ignored.
+        let required = (1 << (k - i).min(8)) - 1;
+        if _mm256_movemask_ps(_mm256_castsi256_ps(hit)) as u32 & required != required {
+            return false;
+        }
+        i += 8;
+    }
+
+    true
+}
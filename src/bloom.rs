@@ -1,34 +1,217 @@
-use murmur3::murmur3_32;
 use bit_vec::BitVec;
 
-use std::io::{Cursor, Read};
-use std::f64::consts::{LN_2, E};
+use error::BloomError;
+use frozen::FrozenBloomFilter;
+use hash::{BloomHasher, Murmur3};
 
-/// A trait for hashing an arbitrary stream of bytes into a bloom filter.
-pub trait BloomHasher {
-    /// Returns the hashed value of the bytes given some seed.
-    #[inline]
-    fn hash(&self, seed: u32, bytes: &[u8]) -> u32;
+use math;
+use view::BloomFilterView;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::hint;
+use core::mem;
+use core::ops::{BitAnd, BitOr, BitXor};
+use core::f64::consts::LN_2;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "std")]
+use core::hash::BuildHasher;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "compression")]
+mod compression;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "serde")]
+mod serde_impl;
+
+#[cfg(feature = "mmap")]
+pub use self::mmap::MmapBloomFilter;
+
+/// The expected number of elements of a default `BloomFilter`.
+const DEFAULT_MAX_ELEMENTS: u64 = 1000;
+
+/// The error rate of a default `BloomFilter`.
+const DEFAULT_ERROR_RATE: f64 = 0.01;
+
+/// The magic bytes at the start of the binary format.
+const MAGIC: &[u8; 4] = b"BLMF";
+
+/// The version of the binary format.
+const VERSION: u8 = 2;
+
+/// The length of the header of the binary format.
+const HEADER_LEN: usize = 29;
+
+/// The size of a memory page on most platforms, used when touching every page
+/// of a filter upfront.
+const PAGE_LEN: usize = 4096;
+
+/// The number of bytes of bits read or written at once when streaming the
+/// binary format, a multiple of the 4 byte blocks of a `BitVec`.
+#[cfg(feature = "std")]
+const STREAM_CHUNK_LEN: usize = 64 * 1024;
+
+/// The health of a `BloomFilter`, as returned by `BloomFilter::health`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterHealth {
+    /// The estimated false positive rate is within the target error rate.
+    Ok,
+    /// The estimated false positive rate, included, exceeds the target error
+    /// rate. The filter should be rebuilt with a larger capacity.
+    Degraded(f64),
+    /// The estimated false positive rate has reached 50%, so the filter no
+    /// longer filters meaningfully.
+    Saturated,
+}
+
+/// The probes of a single query, as returned by `BloomFilter::explain_contains`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeReport {
+    probes: Vec<(usize, bool)>,
+}
+
+impl ProbeReport {
+    /// Returns the index of every probe and whether its bit is set, in the
+    /// order the probes are computed.
+    pub fn probes(&self) -> &[(usize, bool)] {
+        &self.probes
+    }
+
+    /// Returns an iterator over the index of every probe.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.probes.iter().map(|&(index, _)| index)
+    }
+
+    /// Returns `true` if every probed bit is set, which is the result of
+    /// `contains`.
+    pub fn is_hit(&self) -> bool {
+        self.probes.iter().all(|&(_, set)| set)
+    }
+}
+
+/// The parameters of a `BloomFilter` without its bits, as returned by
+/// `BloomFilter::parameters`.
+///
+/// This is a small manifest that can be shipped separately from the bits of
+/// a filter, so that consumers can check compatibility before fetching them.
+/// With the `serde` feature it implements `Serialize` and `Deserialize`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FilterParameters {
+    k: u32,
+    bit_len: u64,
+    hasher: String,
+    seed_offset: u32,
+}
+
+impl FilterParameters {
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the size of the bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.bit_len
+    }
+
+    /// Returns the type name of the hasher, as given by
+    /// `core::any::type_name`.
+    ///
+    /// Type names are not guaranteed to be stable across compiler versions,
+    /// so they identify the hasher for validation but should not be parsed.
+    pub fn hasher(&self) -> &str {
+        &self.hasher
+    }
+
+    /// Returns the seed offset used when hashing elements, which is also the
+    /// salt of salted filters.
+    pub fn seed_offset(&self) -> u32 {
+        self.seed_offset
+    }
+
+    /// Returns `true` if filters with these parameters and `other` hash
+    /// elements identically and share the dimensions of their bit arrays, so
+    /// that they can be combined or queried interchangeably.
+    pub fn is_compatible(&self, other: &FilterParameters) -> bool {
+        self == other
+    }
+}
+
+/// Statistics of the inserts into a `BloomFilter`, as returned by
+/// `BloomFilter::stats`.
+///
+/// Only probes of inserts are counted, bits set by combining filters are not.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FilterStats {
+    bits_set: u64,
+    already_set: u64,
 }
 
-/// A unit struct for the murmur3 hash function.
-pub struct Murmur3;
+#[cfg(feature = "stats")]
+impl FilterStats {
+    /// Returns the number of probes that set a bit which was unset.
+    pub fn bits_set(&self) -> u64 {
+        self.bits_set
+    }
+
+    /// Returns the number of probes that hit a bit which was already set.
+    pub fn already_set(&self) -> u64 {
+        self.already_set
+    }
+
+    /// Returns the fraction of probes that hit a bit which was already set,
+    /// or `0.0` if nothing was inserted.
+    ///
+    /// This rises as the filter fills up, and approaches 1 when most inserted
+    /// elements were probably already present.
+    pub fn already_set_ratio(&self) -> f64 {
+        let probes = self.bits_set + self.already_set;
+        if probes == 0 {
+            return 0_f64;
+        }
+
+        self.already_set as f64 / probes as f64
+    }
 
-impl BloomHasher for Murmur3 {
-    fn hash(&self, seed: u32, bytes: &[u8]) -> u32 {
-        let mut cursor = Cursor::new(bytes);
-        murmur3_32(cursor.by_ref(), seed)
+    /// Count a probe, which set a bit if `new` is `true`.
+    #[inline]
+    fn record(&mut self, new: bool) {
+        if new {
+            self.bits_set += 1;
+        } else {
+            self.already_set += 1;
+        }
     }
 }
 
 /// BloomFilter
 ///
 /// An implementation of a bloom filter
+///
+/// A `BloomFilter` is `Sync` whenever its hasher is, so it can be queried
+/// from many threads through a shared reference. See `AtomicBloomFilter` for
+/// inserting concurrently as well.
+#[derive(Clone)]
 pub struct BloomFilter<T> {
     hasher: T,
     k: u32,
+    seed_offset: u32,
     bit_vec: BitVec,
     insert_count: u64,
+    target_error_rate: f64,
+    #[cfg(feature = "stats")]
+    stats: FilterStats,
 }
 
 impl<T: BloomHasher> BloomFilter<T> {
@@ -39,13 +222,32 @@ impl<T: BloomHasher> BloomFilter<T> {
     /// Typically, this function should not be called directly unless,
     /// the optimal number of hash functions and optimal array size are
     /// already known.
+    ///
+    /// Both `k` and `array_size` are clamped to a minimum of 1, since a filter
+    /// without any hash functions or bits cannot answer queries meaningfully.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `array_size` does not fit in a `usize`, as on 32-bit targets.
+    /// See `try_new` for a non-panicking alternative.
     pub fn new(hasher: T, k: u32, array_size: u64) -> Self {
-        Self {
+        Self::try_new(hasher, k, array_size).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a new `BloomFilter` as with `new`, returning
+    /// `BloomError::CapacityTooLarge` if `array_size` does not fit in a
+    /// `usize`, instead of truncating it.
+    pub fn try_new(hasher: T, k: u32, array_size: u64) -> Result<Self, BloomError> {
+        Ok(Self {
             hasher,
-            k,
-            bit_vec: BitVec::from_elem(array_size as usize, false),
+            k: k.max(1),
+            seed_offset: 0,
+            bit_vec: BitVec::from_elem(checked_len(array_size.max(1))?, false),
             insert_count: 0,
-        }
+            target_error_rate: DEFAULT_ERROR_RATE,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
     }
 
     /// Create a `BloomFilter` by computing its optimal parameters.
@@ -58,35 +260,430 @@ impl<T: BloomHasher> BloomFilter<T> {
     /// ```text
     /// m / n * ln(2)
     /// ```
+    /// rounded to the nearest integer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    /// See `try_optimal` for a non-panicking alternative.
     pub fn optimal(hasher: T, max_elements: u64, error_rate: f64) -> Self {
-        // Check error rate is valid
-        if error_rate <= 0_f64 || error_rate >= 1_f64 {
-            panic!("Error rate must be 0 <= error_rate < 1");
+        Self::try_optimal(hasher, max_elements, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a `BloomFilter` by computing its optimal parameters, returning an
+    /// error if the parameters are invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomError, BloomFilter, Murmur3};
+    ///
+    /// assert!(BloomFilter::try_optimal(Murmur3, 1000, 0.01).is_ok());
+    /// assert_eq!(
+    ///     Some(BloomError::InvalidErrorRate(1.5)),
+    ///     BloomFilter::try_optimal(Murmur3, 1000, 1.5).err()
+    /// );
+    /// ```
+    pub fn try_optimal(hasher: T, max_elements: u64, error_rate: f64) -> Result<Self, BloomError> {
+        let (k, m) = optimal_parameters(max_elements, error_rate)?;
+
+        // Create the bloom filter
+        Ok(Self::new(hasher, k, m).with_target_error_rate(error_rate))
+    }
+
+    /// Create a `BloomFilter` by computing its optimal parameters, with `salt`
+    /// XORed into the seeds used when hashing elements.
+    ///
+    /// Without a salt the probes of every element are fully determined by the
+    /// configuration of the filter, so an adversary knowing it can choose keys
+    /// that collide. A secret salt makes the probes unpredictable. The salt is
+    /// stored as the seed offset, see `with_seed_offset`, so it is persisted
+    /// by `to_bytes` and the other serialization formats.
+    ///
+    /// Murmur3 does not resist attacks that know its output for some inputs,
+    /// so filters built from untrusted input should prefer the keyed
+    /// `SipHash13` hasher.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    pub fn optimal_salted(hasher: T, max_elements: u64, error_rate: f64, salt: u32) -> Self {
+        Self::optimal(hasher, max_elements, error_rate).with_seed_offset(salt)
+    }
+
+    /// Create a `BloomFilter` by computing its optimal parameters, with a
+    /// randomly generated salt, see `optimal_salted`.
+    ///
+    /// The salt is generated from the randomly keyed `RandomState` of the
+    /// standard library.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    #[cfg(feature = "std")]
+    pub fn new_random(hasher: T, max_elements: u64, error_rate: f64) -> Self {
+        let salt = RandomState::new().build_hasher().finish() as u32;
+        Self::optimal_salted(hasher, max_elements, error_rate, salt)
+    }
+
+    /// Create the smallest `BloomFilter` with exactly `k` hash functions whose
+    /// error rate stays below `error_rate` for `max_elements` elements.
+    ///
+    /// This is useful when `k` is fixed by an external specification. The array
+    /// size is solved from the false positive rate for a fixed `k`
+    /// ```text
+    /// -(k * n) / ln(1 - p ^ (1 / k))
+    /// ```
+    /// and rounded up. Any `k` can meet any error rate given enough bits, but
+    /// far from the optimal `k` the array size grows quickly, so
+    /// `BloomError::CapacityTooLarge` is returned if it cannot be allocated.
+    ///
+    /// Returns `BloomError::ZeroHashFunctions` if `k` is zero, and the same
+    /// errors as `try_optimal` for invalid `max_elements` or `error_rate`.
+    pub fn optimal_with_k(
+        hasher: T,
+        max_elements: u64,
+        error_rate: f64,
+        k: u32,
+    ) -> Result<Self, BloomError> {
+        let m = array_size_for_k(max_elements, error_rate, k)?;
+        Ok(Self::new(hasher, k, m).with_target_error_rate(error_rate))
+    }
+
+    /// Create a `BloomFilter` by computing its optimal parameters, with the
+    /// array size rounded up to the next power of two.
+    ///
+    /// Indices into a power of two array are computed with a shift of the hash
+    /// instead of a wide multiplication, which makes every probe slightly
+    /// cheaper. Neither has a modulo bias, and both give the same indices. The
+    /// number of hash functions is recomputed for the rounded array size.
+    ///
+    /// Rounding up costs up to twice the memory of `optimal`, which in turn
+    /// lowers the false positive rate below `error_rate`. The saving per probe
+    /// is small, around 1% of a lookup for a filter that fits in cache, and a
+    /// larger array that no longer fits in cache is slower overall, so this is
+    /// only worthwhile when the extra memory is cheap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    /// See `try_optimal_pow2` for a non-panicking alternative.
+    pub fn optimal_pow2(hasher: T, max_elements: u64, error_rate: f64) -> Self {
+        Self::try_optimal_pow2(hasher, max_elements, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a `BloomFilter` with a power of two array size by computing its
+    /// optimal parameters, returning an error if the parameters are invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let bloom_filter = BloomFilter::try_optimal_pow2(Murmur3, 10000, 0.01).unwrap();
+    ///
+    /// assert_eq!(131072, bloom_filter.capacity_bits());
+    /// assert!(bloom_filter.projected_false_positive_rate(10000) < 0.01);
+    /// ```
+    pub fn try_optimal_pow2(hasher: T, max_elements: u64, error_rate: f64) -> Result<Self, BloomError> {
+        let (_, m) = optimal_parameters(max_elements, error_rate)?;
+        let m = m.checked_next_power_of_two().ok_or(BloomError::CapacityTooLarge)?;
+        let m = check_array_size(m, usize::MAX as u64)?;
+
+        let k = optimal_hash_functions(m, max_elements);
+        Ok(Self::new(hasher, k, m).with_target_error_rate(error_rate))
+    }
+
+    /// Create a `BloomFilter` using exactly `bytes` bytes of bits, with the
+    /// optimal number of hash functions for `max_elements` elements.
+    ///
+    /// This is useful when memory rather than the error rate is the constraint.
+    /// The resulting error rate once `max_elements` elements are inserted is
+    /// given by `projected_false_positive_rate(max_elements)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let bloom_filter = BloomFilter::with_memory(Murmur3, 1024, 1000);
+    ///
+    /// assert_eq!(8192, bloom_filter.capacity_bits());
+    /// assert!(bloom_filter.projected_false_positive_rate(1000) < 0.02);
+    /// ```
+    pub fn with_memory(hasher: T, bytes: u64, max_elements: u64) -> Self {
+        let m = bytes.saturating_mul(8);
+        Self::new(hasher, optimal_hash_functions(m, max_elements.max(1)), m)
+    }
+
+    /// Create a `BloomFilter` with `fill_ratio` of its bits set at random, as
+    /// if elements had been inserted.
+    ///
+    /// This is intended for benchmarking lookups against a filter at a given
+    /// fill ratio without the cost of hashing and inserting real data. The bits
+    /// are chosen by a PRNG seeded with `seed`, so the same arguments always
+    /// produce the same filter. `k` and `array_size` are clamped to at least 1,
+    /// as in `new`, and the insert count is zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fill_ratio` is not within `[0, 1]`.
+    pub fn with_random_fill(hasher: T, k: u32, array_size: u64, fill_ratio: f64, seed: u64) -> Self {
+        assert!(
+            (0_f64..=1_f64).contains(&fill_ratio),
+            "fill ratio must be within [0, 1], got {}",
+            fill_ratio
+        );
+
+        let mut bloom_filter = Self::new(hasher, k, array_size);
+        let len = bloom_filter.bit_vec.len();
+        let target = math::round(fill_ratio * len as f64) as usize;
+
+        let mut state = seed;
+        let mut set = 0;
+        while set < target {
+            let index = fastrange(splitmix64(&mut state), len);
+            if !bloom_filter.bit_vec[index] {
+                bloom_filter.bit_vec.set(index, true);
+                set += 1;
+            }
         }
 
-        // Calculate the length of the bit vector
-        let m = optimal_vec_size(max_elements as u64, error_rate);
+        bloom_filter
+    }
 
-        // Calculate the number of hash functions to use
-        let k = optimal_hash_functions(m, max_elements as u64);
+    /// Create an optimally sized `BloomFilter` containing every item of `iter`.
+    ///
+    /// The items are collected first, since the number of elements must be known
+    /// to compute the optimal parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)`.
+    pub fn from_iter<I, B>(iter: I, hasher: T, error_rate: f64) -> Self
+    where
+        I: IntoIterator<Item = B>,
+        B: AsRef<[u8]>,
+    {
+        let items: Vec<B> = iter.into_iter().collect();
 
-        // Create the bloom filter
-        Self::new(hasher, k, m)
+        let mut bloom_filter = Self::optimal(hasher, (items.len() as u64).max(1), error_rate);
+        bloom_filter.insert_all(&items);
+        bloom_filter
+    }
+
+    /// Create a fresh, optimally sized `BloomFilter` containing `items`.
+    ///
+    /// This is intended for shrinking an over-provisioned filter once its real
+    /// cardinality is known, for example from `estimate_cardinality`. A bloom
+    /// filter cannot be resized, since its bits cannot be mapped back to the
+    /// elements that set them, so the original elements must be provided.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)`.
+    pub fn rebuild_from<B: AsRef<[u8]>>(items: &[B], hasher: T, error_rate: f64) -> Self {
+        let mut bloom_filter = Self::optimal(hasher, (items.len() as u64).max(1), error_rate);
+        bloom_filter.insert_all(items);
+        bloom_filter
+    }
+
+    /// Create a `BloomFilter` from an existing bit array, such as one produced
+    /// by another system.
+    ///
+    /// The filter uses a seed offset of zero, see `with_seed_offset` to
+    /// change it. Returns `BloomError::ZeroHashFunctions` if `k` is zero and
+    /// `BloomError::ZeroArraySize` if `bit_vec` is empty.
+    pub fn from_parts(
+        hasher: T,
+        k: u32,
+        bit_vec: BitVec,
+        insert_count: u64,
+    ) -> Result<Self, BloomError> {
+        if k == 0 {
+            return Err(BloomError::ZeroHashFunctions);
+        }
+        if bit_vec.is_empty() {
+            return Err(BloomError::ZeroArraySize);
+        }
+
+        Ok(Self {
+            hasher,
+            k,
+            seed_offset: 0,
+            bit_vec,
+            insert_count,
+            target_error_rate: DEFAULT_ERROR_RATE,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
+    }
+
+    /// Create a `BloomFilterView` operating on the `bit_len` bits of an
+    /// externally owned `buffer` starting at `bit_offset`, instead of on its
+    /// own bit array.
+    ///
+    /// This allows packing many filters into a single allocation, each using
+    /// its own range of the buffer. `k` is clamped to a minimum of 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_len` is zero or the range does not fit in `buffer`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut buffer = vec![0; 256];
+    ///
+    /// let mut first = BloomFilter::in_buffer(Murmur3, 7, 0, 1024, &mut buffer);
+    /// first.insert(b"Hello");
+    /// assert!(first.contains(b"Hello"));
+    ///
+    /// let second = BloomFilter::in_buffer(Murmur3, 7, 1024, 1024, &mut buffer);
+    /// assert!(!second.contains(b"Hello"));
+    /// ```
+    pub fn in_buffer(
+        hasher: T,
+        k: u32,
+        bit_offset: usize,
+        bit_len: usize,
+        buffer: &mut [u8],
+    ) -> BloomFilterView<'_, T> {
+        BloomFilterView::new(hasher, k, bit_offset, bit_len, buffer)
     }
 
     /// Insert a slice of bytes into the `BloomFilter`.
-    pub fn insert(&mut self, bytes: &[u8]) {
-        for seed in 0..self.k {
-            let hash = self.hasher.hash(seed, bytes) as usize % self.bit_vec.len();
-            self.bit_vec.set(hash, true);
+    ///
+    /// Returns `true` if at least one bit was flipped, which guarantees the
+    /// element was not present before. Returns `false` if the element was
+    /// probably already present, subject to the same false positives as
+    /// `contains`.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) -> bool {
+        let probes = self.probes(bytes.as_ref());
+        self.insert_probes(probes)
+    }
+
+    /// Insert an element given the two base hashes of the double hashing
+    /// scheme, without hashing anything.
+    ///
+    /// This is intended for pipelines that already hash their keys, for
+    /// example for sharding. The hashes must be derived exactly as the filter
+    /// derives them, otherwise membership queries will be wrong:
+    /// ```text
+    /// hash = hasher.hash128(seed_offset, bytes)
+    /// h1 = hash as u64
+    /// h2 = (hash >> 64) as u64
+    /// ```
+    /// For hashers without a native 128-bit hash, `h2` is
+    /// `hasher.hash64(seed_offset, bytes)` with its 32-bit halves swapped.
+    /// Returns `true` if at least one bit was flipped, as `insert` does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, BloomHasher, Murmur3};
+    ///
+    /// let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+    ///
+    /// let hash = Murmur3.hash128(bloom_filter.seed_offset(), b"Hello");
+    /// bloom_filter.insert_precomputed(hash as u64, (hash >> 64) as u64);
+    ///
+    /// assert!(bloom_filter.contains(b"Hello"));
+    /// ```
+    pub fn insert_precomputed(&mut self, h1: u64, h2: u64) -> bool {
+        let probes = Probes::from_hashes(h1, h2, self.k, self.bit_vec.len());
+        self.insert_probes(probes)
+    }
+
+    /// Set the bits of `probes`, returning `true` if any of them was unset.
+    fn insert_probes(&mut self, probes: Probes) -> bool {
+        let mut new = false;
+        for index in probes {
+            new |= self.set_bit(index);
         }
         self.insert_count += 1;
+        new
+    }
+
+    /// Set the bit at `index`, returning `true` if it was unset.
+    #[inline]
+    fn set_bit(&mut self, index: usize) -> bool {
+        let new = !self.bit_vec[index];
+        if new {
+            self.bit_vec.set(index, true);
+        }
+        #[cfg(feature = "stats")]
+        self.stats.record(new);
+        new
+    }
+
+    /// Check whether a slice of bytes exists in the `BloomFilter`, then insert
+    /// it.
+    ///
+    /// Returns what `contains` would have returned before inserting, hashing
+    /// the element only once. This is the opposite of the result of `insert`,
+    /// and is convenient for deduplication loops.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+    ///
+    /// assert!(!bloom_filter.check_and_insert("Hello"));
+    /// assert!(bloom_filter.check_and_insert("Hello"));
+    /// ```
+    pub fn check_and_insert<B: AsRef<[u8]>>(&mut self, bytes: B) -> bool {
+        !self.insert(bytes)
     }
 
     /// Insert a slice of slices of bytes into the `BloomFilter`.
     pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
         for item in slice {
-            self.insert(item.as_ref());
+            self.insert(item);
+        }
+    }
+
+    /// Insert every element of an iterator into the `BloomFilter`.
+    ///
+    /// Unlike `insert_all` this consumes the iterator directly, so streaming
+    /// sources such as the lines of a file do not need to be collected first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+    ///
+    /// bloom_filter.insert_iter((0..10_u32).map(|i| i.to_le_bytes()));
+    ///
+    /// assert!(bloom_filter.contains(7_u32.to_le_bytes()));
+    /// ```
+    pub fn insert_iter<I, B>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = B>,
+        B: AsRef<[u8]>,
+    {
+        for item in iter {
+            self.insert(item);
         }
     }
 
@@ -114,72 +711,3088 @@ impl<T: BloomHasher> BloomFilter<T> {
     /// }
     /// ```
     pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
-        for seed in 0..self.k {
-            let hash = self.hasher.hash(seed, bytes.as_ref()) as usize % self.bit_vec.len();
-            if !self.bit_vec[hash] {
-                return false;
-            }
-        }
-
-        true
+        self.probes(bytes.as_ref()).all(|index| self.bit_vec[index])
     }
 
-    /// Calculate the expected false positive rate given the current state of
-    /// the `BloomFilter`.
-    pub fn false_positive_rate(&self) -> f64 {
-        false_positive_rate(self.insert_count, self.bit_vec.len() as u64, self.k)
+    /// Check whether an element exists in the `BloomFilter` given the two base
+    /// hashes of the double hashing scheme.
+    ///
+    /// See `insert_precomputed` for how the hashes must be derived.
+    pub fn contains_precomputed(&self, h1: u64, h2: u64) -> bool {
+        Probes::from_hashes(h1, h2, self.k, self.bit_vec.len()).all(|index| self.bit_vec[index])
     }
-}
-
-/// This function computes the false positive rate given n, m, and k.
-#[inline]
-fn false_positive_rate(n: u64, m: u64, k: u32) -> f64 {
-    (1_f64 - E.powf(-(n as f64) * m as f64 / k as f64)).powf(k as f64)
-}
-
-#[inline]
-fn optimal_hash_functions(m: u64, n: u64) -> u32 {
-    1_f64.max(m as f64 / n as f64 * LN_2).ceil() as u32
-}
-
-#[inline]
-fn optimal_vec_size(n: u64, p: f64) -> u64 {
-    (-(n as f64 * p.ln()) / LN_2.powi(2)).ceil() as u64
-}
 
-#[cfg(test)]
-mod tests {
-    use std::fs::File;
-    use std::io::{BufReader, BufRead};
-    use super::*;
+    /// Insert an element given an externally computed 64-bit hash, without
+    /// hashing anything.
+    ///
+    /// The `k` probes are derived from `hash` by double hashing, using `hash`
+    /// and `hash` with its 32-bit halves swapped as the two base hashes. This
+    /// lets a key be hashed once and inserted into several filters. For
+    /// hashers without a native 128-bit hash, such as `Murmur3`, this matches
+    /// inserting the bytes when `hash` is `hasher.hash64(seed_offset, bytes)`.
+    /// Returns `true` if at least one bit was flipped, as `insert` does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, BloomHasher, Murmur3};
+    ///
+    /// let hash = Murmur3.hash64(0, b"Hello");
+    ///
+    /// let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+    /// let mut b = BloomFilter::optimal(Murmur3, 1000, 0.01);
+    /// a.insert_u64_hash(hash);
+    /// b.insert_u64_hash(hash);
+    ///
+    /// assert!(a.contains(b"Hello"));
+    /// assert!(b.contains_u64_hash(hash));
+    /// ```
+    pub fn insert_u64_hash(&mut self, hash: u64) -> bool {
+        let probes = Probes::from_hash(hash, self.k, self.bit_vec.len());
+        self.insert_probes(probes)
+    }
 
-    #[test]
-    fn test_optimal_hash_functions() {
-        assert_eq!(1, optimal_hash_functions(1, 10));
-        assert_eq!(7, optimal_hash_functions(95851, 10000));
-        assert_eq!(7, optimal_hash_functions(9586, 1000));
-        assert_eq!(7, optimal_hash_functions(90000, 10000));
+    /// Check whether an element exists in the `BloomFilter` given an
+    /// externally computed 64-bit hash.
+    ///
+    /// See `insert_u64_hash` for how the probes are derived.
+    pub fn contains_u64_hash(&self, hash: u64) -> bool {
+        Probes::from_hash(hash, self.k, self.bit_vec.len()).all(|index| self.bit_vec[index])
+    }
+
+    /// Insert any value implementing `Hash` into the `BloomFilter`.
+    ///
+    /// The bytes written by the `Hash` implementation of `value` are collected
+    /// and inserted as with `insert`, so they are hashed by the filter's own
+    /// `BloomHasher`. The `Hash` implementations of integers write their bytes
+    /// in native endianness, so filters containing them should not be shared
+    /// between platforms of different endianness.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+    ///
+    /// bloom_filter.insert_hashable(&(42_u64, "answer"));
+    ///
+    /// assert!(bloom_filter.contains_hashable(&(42_u64, "answer")));
+    /// ```
+    pub fn insert_hashable<H: Hash>(&mut self, value: &H) -> bool {
+        self.insert(hash_bytes(value))
+    }
+
+    /// Check whether a value implementing `Hash` exists in the `BloomFilter`.
+    ///
+    /// See `insert_hashable`.
+    pub fn contains_hashable<H: Hash>(&self, value: &H) -> bool {
+        self.contains(hash_bytes(value))
+    }
+
+    /// Insert a `u64` into the `BloomFilter`, as its 8 little-endian bytes.
+    ///
+    /// Unlike `insert_hashable`, the encoding does not depend on the platform,
+    /// so filters containing integers can be shared between platforms of any
+    /// endianness. Narrower integers should be widened to `u64` first, so that
+    /// the same value always maps to the same bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+    ///
+    /// bloom_filter.insert_u64(42);
+    ///
+    /// assert!(bloom_filter.contains_u64(42));
+    /// assert!(bloom_filter.contains(42_u64.to_le_bytes()));
+    /// ```
+    pub fn insert_u64(&mut self, value: u64) -> bool {
+        self.insert(value.to_le_bytes())
+    }
+
+    /// Check whether a `u64` exists in the `BloomFilter`, see `insert_u64`.
+    pub fn contains_u64(&self, value: u64) -> bool {
+        self.contains(value.to_le_bytes())
+    }
+
+    /// Insert an `i64` into the `BloomFilter`, as its 8 little-endian bytes in
+    /// two's complement.
+    ///
+    /// See `insert_u64`. A non-negative `i64` maps to the same bits as the
+    /// equal `u64`.
+    pub fn insert_i64(&mut self, value: i64) -> bool {
+        self.insert(value.to_le_bytes())
+    }
+
+    /// Check whether an `i64` exists in the `BloomFilter`, see `insert_i64`.
+    pub fn contains_i64(&self, value: i64) -> bool {
+        self.contains(value.to_le_bytes())
+    }
+
+    /// Check whether every item of a slice exists in the `BloomFilter`.
+    ///
+    /// Stops at the first item that is not contained.
+    pub fn contains_all<B: AsRef<[u8]>>(&self, items: &[B]) -> bool {
+        items.iter().all(|item| self.contains(item))
+    }
+
+    /// Check whether at least one item of a slice exists in the `BloomFilter`.
+    ///
+    /// Stops at the first item that is contained.
+    pub fn contains_any<B: AsRef<[u8]>>(&self, items: &[B]) -> bool {
+        items.iter().any(|item| self.contains(item))
+    }
+
+    /// Returns the number of distinct bits `bytes` maps to, which is at most
+    /// `k`.
+    ///
+    /// Two of the `k` indices of an element can coincide, most often in small
+    /// arrays, which effectively lowers `k` for that element and raises the
+    /// false positive rate. This is a debugging aid for diagnosing such
+    /// configurations.
+    pub fn distinct_probe_count<B: AsRef<[u8]>>(&self, bytes: B) -> usize {
+        let mut indices: Vec<usize> = self.probes(bytes.as_ref()).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices.len()
+    }
+
+    /// Returns the index and bit value of every probe of `bytes`, for
+    /// investigating why a query returned a false positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+    /// bloom_filter.insert(b"Hello");
+    ///
+    /// let report = bloom_filter.explain_contains(b"Hello");
+    /// assert!(report.is_hit());
+    /// assert_eq!(bloom_filter.num_hashes() as usize, report.probes().len());
+    /// ```
+    pub fn explain_contains<B: AsRef<[u8]>>(&self, bytes: B) -> ProbeReport {
+        ProbeReport {
+            probes: self
+                .probes(bytes.as_ref())
+                .map(|index| (index, self.bit_vec[index]))
+                .collect(),
+        }
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the size of the underlying bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.bit_vec.len() as u64
+    }
+
+    /// Returns the approximate number of bytes of memory used by the
+    /// `BloomFilter`.
+    ///
+    /// This is the size of the `u32` blocks of the bit array, `ceil(m / 32) * 4`
+    /// bytes, plus the size of the `BloomFilter` struct itself. Memory owned by
+    /// the hasher is not included.
+    pub fn memory_bytes(&self) -> usize {
+        mem::size_of_val(self.bit_vec.storage()) + mem::size_of::<Self>()
+    }
+
+    /// Returns the underlying bit array.
+    pub fn bit_vec(&self) -> &BitVec {
+        &self.bit_vec
+    }
+
+    /// Returns the seed offset used when hashing elements.
+    pub fn seed_offset(&self) -> u32 {
+        self.seed_offset
+    }
+
+    /// Returns the hasher used when hashing elements.
+    pub fn hasher(&self) -> &T {
+        &self.hasher
+    }
+
+    /// Set the seed offset used when hashing elements, for compatibility with
+    /// filters produced by other systems.
+    ///
+    /// Elements are hashed using the seeds `seed_offset` and `seed_offset + 1`
+    /// instead of `0` and `1`. The offset must be set before anything is
+    /// inserted, since elements inserted with a different offset are no
+    /// longer found.
+    pub fn with_seed_offset(mut self, seed_offset: u32) -> Self {
+        self.seed_offset = seed_offset;
+        self
+    }
+
+    /// Replace the hasher used when hashing elements, keeping the dimensions
+    /// and seed offset of the filter.
+    ///
+    /// Elements inserted with the previous hasher would no longer be found, so
+    /// the hasher can only be replaced while no bits are set, for example to
+    /// substitute a mock hasher in tests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any bit of the filter is set.
+    pub fn with_hasher<U: BloomHasher>(self, hasher: U) -> BloomFilter<U> {
+        assert!(
+            self.count_set_bits() == 0,
+            "cannot replace the hasher of a non-empty bloom filter"
+        );
+
+        BloomFilter {
+            hasher,
+            k: self.k,
+            seed_offset: self.seed_offset,
+            bit_vec: self.bit_vec,
+            insert_count: self.insert_count,
+            target_error_rate: self.target_error_rate,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        }
+    }
+
+    /// Returns the error rate the `BloomFilter` was sized for, which `health`
+    /// compares against.
+    ///
+    /// This is the error rate given to `optimal` and the other sizing
+    /// constructors. Filters created otherwise, including deserialized
+    /// filters, since the target is not serialized, default to 1%.
+    pub fn target_error_rate(&self) -> f64 {
+        self.target_error_rate
+    }
+
+    /// Set the error rate `health` compares against, see `target_error_rate`.
+    pub fn with_target_error_rate(mut self, target_error_rate: f64) -> Self {
+        self.target_error_rate = target_error_rate;
+        self
+    }
+
+    /// Returns the health of the `BloomFilter`, comparing its estimated false
+    /// positive rate against `target_error_rate`.
+    ///
+    /// The false positive rate is estimated as `fill_ratio ^ k`, the chance
+    /// that all `k` bits of an absent element are set. This accounts for
+    /// duplicate inserts, unlike `false_positive_rate`, which assumes every
+    /// insert was a new element. The filter is `Saturated` once the estimate
+    /// reaches 50%, at which point a query for an absent element is at least
+    /// as likely to be wrong as right.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, FilterHealth, Murmur3};
+    ///
+    /// let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+    /// assert_eq!(FilterHealth::Ok, bloom_filter.health());
+    ///
+    /// for i in 0..1000_u64 {
+    ///     bloom_filter.insert_u64(i);
+    /// }
+    /// assert_eq!(FilterHealth::Saturated, bloom_filter.health());
+    /// ```
+    pub fn health(&self) -> FilterHealth {
+        let rate = math::powf(self.fill_ratio(), self.k as f64);
+
+        if rate >= 0.5 {
+            FilterHealth::Saturated
+        } else if rate > self.target_error_rate {
+            FilterHealth::Degraded(rate)
+        } else {
+            FilterHealth::Ok
+        }
+    }
+
+    /// Returns the statistics of the inserts into the `BloomFilter`.
+    ///
+    /// Statistics are counted from the creation of the filter, or the last
+    /// `clear`, they are not serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut bloom_filter = BloomFilter::new(Murmur3, 7, 1000);
+    /// bloom_filter.insert(b"Hello");
+    /// bloom_filter.insert(b"Hello");
+    ///
+    /// assert_eq!(0.5, bloom_filter.stats().already_set_ratio());
+    /// ```
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> FilterStats {
+        self.stats
+    }
+
+    /// Returns the number of times `insert` has been called.
+    ///
+    /// Inserting the same element twice counts twice, so this is not the
+    /// number of unique elements in the filter.
+    pub fn len(&self) -> u64 {
+        self.insert_count
+    }
+
+    /// Returns `true` if nothing has been inserted into the `BloomFilter`.
+    pub fn is_empty(&self) -> bool {
+        self.insert_count == 0
+    }
+
+    /// Set the number of inserted elements reported by `len`.
+    ///
+    /// This is useful after `union`, where the summed insert count
+    /// over-estimates the number of elements when the filters overlap, and can
+    /// be combined with `estimate_cardinality`. It only affects reported
+    /// statistics such as `false_positive_rate`, not membership.
+    pub fn set_insert_count(&mut self, n: u64) {
+        self.insert_count = n;
+    }
+
+    /// Remove every element from the `BloomFilter`.
+    ///
+    /// This keeps the number of hash functions and the allocated bit array,
+    /// so the filter can be reused without reallocating.
+    pub fn clear(&mut self) {
+        self.bit_vec.clear();
+        self.insert_count = 0;
+        #[cfg(feature = "stats")]
+        {
+            self.stats = FilterStats::default();
+        }
+    }
+
+    /// Freeze the `BloomFilter` into a read-only `FrozenBloomFilter`, which
+    /// can be queried and serialized but not inserted into.
+    pub fn freeze(self) -> FrozenBloomFilter<T> {
+        FrozenBloomFilter::new(self)
+    }
+
+    /// Touch every page of the underlying bit array, so that the pages are
+    /// faulted in upfront instead of during the first inserts.
+    ///
+    /// Large bit arrays are allocated zeroed, which usually only reserves the
+    /// memory until each page is first written. This does not change the
+    /// contents of the filter, it is purely a latency optimization.
+    pub fn warm(&mut self) {
+        let last = self.bit_vec.len() - 1;
+        for index in (0..last).step_by(PAGE_LEN * 8).chain(Some(last)) {
+            // The bit passes through `black_box` so that writing it back is not
+            // optimized away.
+            let bit = hint::black_box(self.bit_vec[index]);
+            self.bit_vec.set(index, bit);
+        }
+    }
+
+    /// Calculate the expected false positive rate given the current state of
+    /// the `BloomFilter`.
+    pub fn false_positive_rate(&self) -> f64 {
+        false_positive_rate(self.insert_count, self.bit_vec.len() as u64, self.k)
+    }
+
+    /// Calculate the expected false positive rate of the `BloomFilter` after
+    /// `n` elements have been inserted, regardless of its current state.
+    ///
+    /// This is useful for deciding how many elements a configuration can hold
+    /// before its error rate becomes unacceptable.
+    pub fn projected_false_positive_rate(&self, n: u64) -> f64 {
+        false_positive_rate(n, self.bit_vec.len() as u64, self.k)
+    }
+
+    /// Calculate the false positive rate the `BloomFilter` achieves once it
+    /// holds `max_elements` elements, its planned capacity.
+    ///
+    /// `optimal` rounds both `k` and `m`, so the realized rate at capacity
+    /// differs slightly from the requested error rate, usually by a few
+    /// percent of it. This is `projected_false_positive_rate` evaluated at the
+    /// capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01);
+    ///
+    /// assert!((bloom_filter.optimal_false_positive_rate(1000) - 0.01).abs() < 0.0005);
+    /// ```
+    pub fn optimal_false_positive_rate(&self, max_elements: u64) -> f64 {
+        self.projected_false_positive_rate(max_elements)
+    }
+
+    /// Returns the largest number of elements the `BloomFilter` can hold
+    /// before its false positive rate exceeds `target_error`.
+    ///
+    /// This solves the false positive rate for `n` given the array size `m`
+    /// and number of hash functions `k`
+    /// ```text
+    /// -(m / k) * ln(1 - p ^ (1 / k))
+    /// ```
+    /// rounded down. Subtract `len` to get the number of further elements the
+    /// filter can take. Returns 0 if `target_error` is not within `(0, 1)`.
+    pub fn capacity_for_error(&self, target_error: f64) -> u64 {
+        if check_error_rate(target_error).is_err() {
+            return 0;
+        }
+
+        let m = self.bit_vec.len() as f64;
+        let k = self.k as f64;
+        (-(m / k) * math::ln(1_f64 - math::powf(target_error, 1_f64 / k))) as u64
+    }
+
+    /// Returns the number of bits set in the underlying bit array.
+    ///
+    /// This is the primitive that `fill_ratio`, `estimate_cardinality` and
+    /// `estimate_jaccard` build on, and counts 32 bits at a time.
+    pub fn count_ones(&self) -> u64 {
+        self.count_set_bits()
+    }
+
+    /// Returns the fraction of bits set in the underlying bit array.
+    ///
+    /// An optimally configured filter reaches its target error rate when
+    /// roughly half of its bits are set, so a fill ratio approaching 0.5 is a
+    /// good indication the filter should be rebuilt.
+    pub fn fill_ratio(&self) -> f64 {
+        self.count_set_bits() as f64 / self.bit_vec.len() as f64
+    }
+
+    /// Returns the blocks of the underlying bit array, without copying.
+    ///
+    /// `BitVec` stores its bits in `u32` blocks and does not expose a byte view,
+    /// so the storage is returned as is. Bit `i` of the filter is bit `i % 32`
+    /// of block `i / 32`, counting from the least significant bit, and unused
+    /// bits in the final block are zero. Writing every block with
+    /// `u32::to_le_bytes` and truncating to `ceil(bits / 8)` bytes produces the
+    /// same packed bits as `to_bytes`.
+    pub fn as_blocks(&self) -> &[u32] {
+        self.bit_vec.storage()
+    }
+
+    /// Returns an iterator over the indices of every set bit, in ascending order.
+    pub fn set_bits(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bit_vec
+            .blocks()
+            .enumerate()
+            .flat_map(|(i, block)| BlockBits { block, base: i * 32 })
+    }
+
+    /// Estimate the number of distinct elements inserted into the `BloomFilter`.
+    ///
+    /// This uses the estimator by Swamidass and Baldi
+    /// ```text
+    /// -(m / k) * ln(1 - X / m)
+    /// ```
+    /// where `X` is the number of bits set. Unlike `len`, inserting the same
+    /// element twice does not change the estimate.
+    ///
+    /// The estimate is accurate while the filter is well below saturation, it
+    /// becomes increasingly noisy as `X` approaches `m` and is undefined once
+    /// every bit is set. A saturated filter returns the estimate for `m - 1`
+    /// set bits, which is only a lower bound. See `try_estimate_cardinality`
+    /// to detect this case.
+    ///
+    /// Any set bit means at least one element was inserted, so the estimate
+    /// of a non-empty filter is never below `1.0`.
+    pub fn estimate_cardinality(&self) -> f64 {
+        match self.try_estimate_cardinality() {
+            Some(estimate) => estimate,
+            // A filter of a single bit has no fill below saturation.
+            None => self.corrected_cardinality(self.bit_vec.len() as u64 - 1).max(1_f64),
+        }
+    }
+
+    /// Estimate the number of distinct elements inserted into the
+    /// `BloomFilter`, returning `None` if every bit is set.
+    ///
+    /// Once the filter is saturated the estimator of `estimate_cardinality`
+    /// goes to infinity, since any number of further elements would leave the
+    /// bits unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut bloom_filter = BloomFilter::new(Murmur3, 3, 8);
+    /// assert_eq!(Some(0.0), bloom_filter.try_estimate_cardinality());
+    ///
+    /// for i in 0..100_u32 {
+    ///     bloom_filter.insert(i.to_le_bytes());
+    /// }
+    /// assert_eq!(None, bloom_filter.try_estimate_cardinality());
+    /// ```
+    pub fn try_estimate_cardinality(&self) -> Option<f64> {
+        let set_bits = self.count_set_bits();
+        if set_bits == self.bit_vec.len() as u64 {
+            return None;
+        }
+
+        Some(self.corrected_cardinality(set_bits))
+    }
+
+    /// Serialize the `BloomFilter` into a compact binary format.
+    ///
+    /// The hasher is not serialized. The format consists of a 29 byte header
+    /// followed by the packed bits, with all integers stored little-endian:
+    ///
+    /// | Offset | Size             | Description                     |
+    /// |--------|------------------|---------------------------------|
+    /// | 0      | 4                | Magic bytes `BLMF`              |
+    /// | 4      | 1                | Format version, currently `2`   |
+    /// | 5      | 4                | Number of hash functions, `u32` |
+    /// | 9      | 4                | Seed offset, `u32`              |
+    /// | 13     | 8                | Insert count, `u64`             |
+    /// | 21     | 8                | Bit length `m`, `u64`           |
+    /// | 29     | `ceil(m / 8)`    | Packed bits                     |
+    ///
+    /// Bit `i` of the filter is stored in byte `i / 8` of the packed bits at
+    /// position `i % 8`, counting from the least significant bit. Unused bits
+    /// in the final byte are zero.
+    ///
+    /// The format does not depend on the endianness of the host. The `u32`
+    /// blocks of the bit array are stored in host order in memory, but are
+    /// converted to little-endian bytes as they are written, so a filter
+    /// written on a big-endian host is read identically on a little-endian one.
+    ///
+    /// Version `1` of the format has no seed offset, and can still be read.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let bits = pack_bits(&self.bit_vec);
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + bits.len());
+        bytes.extend_from_slice(&self.header());
+        bytes.extend_from_slice(&bits);
+        bytes
+    }
+
+    /// Write the `BloomFilter` to `writer` in the format of `to_bytes`.
+    ///
+    /// The bits are written in chunks, without building a copy of the whole
+    /// serialized filter in memory.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.header())?;
+        self.write_bits(writer)
+    }
+
+    /// Deserialize a `BloomFilter` written by `to_bytes`.
+    ///
+    /// Returns `BloomError::CorruptData` if the magic bytes or version do not
+    /// match, or if `data` is truncated or has trailing bytes. `data` is parsed
+    /// as little-endian regardless of the host.
+    pub fn from_bytes(hasher: T, data: &[u8]) -> Result<Self, BloomError> {
+        let header = Header::parse(data)?;
+
+        Ok(Self {
+            hasher,
+            k: header.k,
+            seed_offset: header.seed_offset,
+            bit_vec: unpack_bits(&data[header.len..], header.bit_len),
+            insert_count: header.insert_count,
+            target_error_rate: DEFAULT_ERROR_RATE,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
+    }
+
+    /// Check whether `other` has the same number of hash functions, seed offset
+    /// and array size as `self`.
+    ///
+    /// Operations combining two filters, such as `union` and `intersect`,
+    /// succeed exactly when this returns `true`.
+    pub fn is_compatible_with(&self, other: &BloomFilter<T>) -> bool {
+        self.check_dimensions(other).is_ok()
+    }
+
+    /// Returns the parameters of the `BloomFilter` without its bits: the
+    /// number of hash functions, the array size, the type of the hasher and
+    /// the seed offset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let a = BloomFilter::optimal(Murmur3, 100, 0.01);
+    /// let b = BloomFilter::optimal(Murmur3, 1000, 0.01);
+    ///
+    /// assert!(a.parameters().is_compatible(&a.clone().parameters()));
+    /// assert!(!a.parameters().is_compatible(&b.parameters()));
+    /// ```
+    pub fn parameters(&self) -> FilterParameters {
+        FilterParameters {
+            k: self.k,
+            bit_len: self.bit_vec.len() as u64,
+            hasher: String::from(any::type_name::<T>()),
+            seed_offset: self.seed_offset,
+        }
+    }
+
+    /// Merge a larger filter into `self` by folding its bits down into the
+    /// array of `self`.
+    ///
+    /// Bloom filter bits cannot be mapped into a larger array, since the filter
+    /// does not know which elements set them, so a smaller filter can never be
+    /// merged into a larger one without re-inserting its elements. The opposite
+    /// direction is exact: if the length of `other` is a multiple `c` of the
+    /// length of `self`, every index `i` of `other` maps to index `i / c` of
+    /// `self`, which is where the same element would have been inserted. The
+    /// result is identical to inserting the elements of both filters into
+    /// `self`.
+    ///
+    /// Returns `BloomError::IncompatibleDimensions` if the number of hash
+    /// functions or seed offset differ, or if the length of `other` is not a
+    /// multiple of the length of `self`. To combine differently sized shards,
+    /// merge them all into a filter whose length divides every shard's length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut small = BloomFilter::new(Murmur3, 7, 1024);
+    /// let mut large = BloomFilter::new(Murmur3, 7, 4096);
+    /// small.insert(b"small");
+    /// large.insert(b"large");
+    ///
+    /// small.merge_rescaled(&large).unwrap();
+    /// assert!(small.contains(b"small"));
+    /// assert!(small.contains(b"large"));
+    ///
+    /// assert!(large.merge_rescaled(&small).is_err());
+    /// ```
+    pub fn merge_rescaled(&mut self, other: &BloomFilter<T>) -> Result<(), BloomError> {
+        let len = self.bit_vec.len();
+        if self.k != other.k
+            || self.seed_offset != other.seed_offset
+            || !other.bit_vec.len().is_multiple_of(len)
+        {
+            return Err(BloomError::IncompatibleDimensions);
+        }
+
+        let factor = other.bit_vec.len() / len;
+        for index in other.set_bits() {
+            self.bit_vec.set(index / factor, true);
+        }
+        self.insert_count = self.insert_count.saturating_add(other.insert_count);
+
+        Ok(())
+    }
+
+    /// Insert every element of `other` into `self`, without allocating a new
+    /// filter as `union` does.
+    ///
+    /// This is convenient for folding many shards into a single accumulator.
+    /// The insert count of `other` is added to that of `self`, so it
+    /// over-estimates the number of elements when the filters overlap.
+    ///
+    /// Both filters must have the same number of hash functions and the same
+    /// array size, otherwise `BloomError::IncompatibleDimensions` is returned
+    /// and `self` is left unchanged.
+    pub fn union_in_place(&mut self, other: &BloomFilter<T>) -> Result<(), BloomError> {
+        self.check_dimensions(other)?;
+
+        self.bit_vec.or(&other.bit_vec);
+        self.insert_count = self.insert_count.saturating_add(other.insert_count);
+
+        Ok(())
+    }
+
+    /// Read a `BloomFilter` written by `to_bytes` or `write_to` from `reader`.
+    ///
+    /// Exactly the bytes of the filter are read, in chunks. Returns an error
+    /// with `io::ErrorKind::InvalidData` if the header is invalid, and
+    /// `io::ErrorKind::UnexpectedEof` if the data is truncated.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(hasher: T, reader: &mut R) -> io::Result<Self> {
+        let invalid_data = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        let mut prefix = [0; 5];
+        reader.read_exact(&mut prefix)?;
+        if &prefix[0..4] != MAGIC {
+            return Err(invalid_data(BloomError::CorruptData));
+        }
+
+        let version = prefix[4];
+        let fields_len = Header::fields_len(version).ok_or(invalid_data(BloomError::CorruptData))?;
+        let mut fields = vec![0; fields_len];
+        reader.read_exact(&mut fields)?;
+        let header = Header::parse_fields(version, &mut &fields[..]).map_err(invalid_data)?;
+
+        let bit_vec = read_bits(reader, header.bit_len)?;
+
+        Ok(Self {
+            hasher,
+            k: header.k,
+            seed_offset: header.seed_offset,
+            bit_vec,
+            insert_count: header.insert_count,
+            target_error_rate: DEFAULT_ERROR_RATE,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
+    }
+
+    /// Write the packed bits of the binary format to `writer`, in chunks.
+    #[cfg(feature = "std")]
+    fn write_bits<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut remaining = self.bit_vec.len().div_ceil(8);
+        let mut buf = Vec::with_capacity(STREAM_CHUNK_LEN);
+        for blocks in self.bit_vec.storage().chunks(STREAM_CHUNK_LEN / 4) {
+            buf.clear();
+            buf.extend(blocks.iter().flat_map(|block| block.to_le_bytes()));
+            buf.truncate(remaining);
+            remaining -= buf.len();
+
+            writer.write_all(&buf)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the header of the binary format for this `BloomFilter`.
+    fn header(&self) -> [u8; HEADER_LEN] {
+        let mut header = [0; HEADER_LEN];
+        header[0..4].copy_from_slice(MAGIC);
+        header[4] = VERSION;
+        header[5..9].copy_from_slice(&self.k.to_le_bytes());
+        header[9..13].copy_from_slice(&self.seed_offset.to_le_bytes());
+        header[13..21].copy_from_slice(&self.insert_count.to_le_bytes());
+        header[21..29].copy_from_slice(&(self.bit_vec.len() as u64).to_le_bytes());
+        header
+    }
+
+    /// Check whether every bit set in `self` is also set in `other`.
+    ///
+    /// This holds whenever every element inserted into `self` was also inserted
+    /// into `other`, so a result of `false` proves some element of `self` is
+    /// missing from `other`. The converse does not hold: bits set in `other` by
+    /// other elements can cover the bits of an element that was never inserted
+    /// into `other`, so `true` does not prove the elements of `self` are a
+    /// subset of those of `other`.
+    ///
+    /// Both filters must have the same number of hash functions and the same
+    /// array size, otherwise `BloomError::IncompatibleDimensions` is returned.
+    pub fn is_subset(&self, other: &BloomFilter<T>) -> Result<bool, BloomError> {
+        self.check_dimensions(other)?;
+
+        Ok(self
+            .bit_vec
+            .blocks()
+            .zip(other.bit_vec.blocks())
+            .all(|(a, b)| a & !b == 0))
+    }
+
+    /// Returns the number of bits set in exactly one of `self` and `other`.
+    ///
+    /// This measures how far two snapshots or replicas of the same filter have
+    /// drifted apart, for example to decide when to resynchronize them.
+    ///
+    /// Both filters must have the same number of hash functions and the same
+    /// array size, otherwise `BloomError::IncompatibleDimensions` is returned.
+    pub fn bit_difference(&self, other: &BloomFilter<T>) -> Result<u64, BloomError> {
+        self.check_dimensions(other)?;
+
+        Ok(self
+            .bit_vec
+            .blocks()
+            .zip(other.bit_vec.blocks())
+            .map(|(a, b)| (a ^ b).count_ones() as u64)
+            .sum())
+    }
+
+    /// Estimate the Jaccard similarity of the elements inserted into `self` and
+    /// `other`, the size of their intersection divided by the size of their
+    /// union.
+    ///
+    /// The sizes of both sets and their union are estimated as with
+    /// `estimate_cardinality`, the union from the bits set in either filter, and
+    /// the intersection is derived from them by inclusion-exclusion. Counting
+    /// the bits set in both filters directly would over-estimate the
+    /// intersection, since bits set by different elements coincide by chance.
+    ///
+    /// The estimate becomes noisy as the filters fill up, since every estimate
+    /// relies on the fraction of unset bits. Once the union of the filters has
+    /// every bit set the estimate is `NaN`. The similarity of two empty filters
+    /// is `1.0`.
+    ///
+    /// Both filters must have the same number of hash functions and the same
+    /// array size, otherwise `BloomError::IncompatibleDimensions` is returned.
+    pub fn estimate_jaccard(&self, other: &BloomFilter<T>) -> Result<f64, BloomError> {
+        self.check_dimensions(other)?;
+
+        let union_bits = self
+            .bit_vec
+            .blocks()
+            .zip(other.bit_vec.blocks())
+            .map(|(a, b)| (a | b).count_ones() as u64)
+            .sum();
+        if union_bits == 0 {
+            return Ok(1_f64);
+        }
+
+        let union = self.cardinality_from_set_bits(union_bits);
+        let intersection = self.estimate_cardinality() + other.estimate_cardinality() - union;
+        Ok((intersection / union).clamp(0_f64, 1_f64))
+    }
+
+    /// Returns the `k` bit indices for `bytes`.
+    ///
+    /// Only two hashes are computed per element, the indices are derived from
+    /// them using the Kirsch-Mitzenmacher double hashing technique.
+    fn probes(&self, bytes: &[u8]) -> Probes {
+        Probes::new(&self.hasher, self.seed_offset, self.k, self.bit_vec.len(), bytes)
+    }
+
+    /// The estimate of `cardinality_from_set_bits`, corrected to be at least
+    /// one element as soon as any bit is set.
+    ///
+    /// A single element sets up to `k` bits, so for fewer set bits the
+    /// uncorrected estimate would be below one element.
+    fn corrected_cardinality(&self, set_bits: u64) -> f64 {
+        let estimate = self.cardinality_from_set_bits(set_bits);
+        if set_bits > 0 {
+            estimate.max(1_f64)
+        } else {
+            estimate
+        }
+    }
+
+    /// Estimate the number of distinct elements that set `set_bits` bits of an
+    /// array with the dimensions of this filter.
+    fn cardinality_from_set_bits(&self, set_bits: u64) -> f64 {
+        let m = self.bit_vec.len() as f64;
+        -(m / self.k as f64) * math::ln(1_f64 - set_bits as f64 / m)
+    }
+
+    /// Returns the number of bits set in the underlying bit array.
+    ///
+    /// `BitVec` keeps the padding bits of its final block zeroed, but they are
+    /// masked off regardless, so they can never skew the statistics.
+    fn count_set_bits(&self) -> u64 {
+        let padding = self.bit_vec.storage().len() * 32 - self.bit_vec.len();
+        let count: u64 = self.bit_vec.blocks().map(|block| block.count_ones() as u64).sum();
+        let padding_count = self
+            .bit_vec
+            .storage()
+            .last()
+            .map_or(0, |block| (block & !(u32::MAX >> padding)).count_ones() as u64);
+        count - padding_count
+    }
+
+    /// Check that `other` has the same number of hash functions, seed offset
+    /// and array size.
+    fn check_dimensions(&self, other: &BloomFilter<T>) -> Result<(), BloomError> {
+        if self.k != other.k
+            || self.seed_offset != other.seed_offset
+            || self.bit_vec.len() != other.bit_vec.len()
+        {
+            return Err(BloomError::IncompatibleDimensions);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: BloomHasher + Clone> BloomFilter<T> {
+    /// Create a new `BloomFilter` containing every element of `self` and `other`.
+    ///
+    /// Both filters must have the same number of hash functions and the same
+    /// array size, otherwise `BloomError::IncompatibleDimensions` is returned.
+    ///
+    /// The insert count of the result is the sum of both insert counts, so it
+    /// over-estimates the number of elements when the two filters overlap.
+    pub fn union(&self, other: &BloomFilter<T>) -> Result<BloomFilter<T>, BloomError> {
+        self.check_dimensions(other)?;
+
+        let mut bit_vec = self.bit_vec.clone();
+        bit_vec.or(&other.bit_vec);
+
+        Ok(Self {
+            hasher: self.hasher.clone(),
+            k: self.k,
+            seed_offset: self.seed_offset,
+            bit_vec,
+            insert_count: self.insert_count.saturating_add(other.insert_count),
+            target_error_rate: self.target_error_rate,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
+    }
+
+    /// Create a new `BloomFilter` approximating the elements present in both
+    /// `self` and `other`.
+    ///
+    /// Both filters must have the same number of hash functions and the same
+    /// array size, otherwise `BloomError::IncompatibleDimensions` is returned.
+    ///
+    /// Every element inserted into both filters is guaranteed to be contained
+    /// in the result, but the result may report false positives at a higher
+    /// rate than either input, since bits set by different elements in each
+    /// filter can coincide. The true size of the intersection cannot be known,
+    /// so the insert count of the result is the smaller of the two insert counts.
+    pub fn intersect(&self, other: &BloomFilter<T>) -> Result<BloomFilter<T>, BloomError> {
+        self.check_dimensions(other)?;
+
+        let mut bit_vec = self.bit_vec.clone();
+        bit_vec.and(&other.bit_vec);
+
+        Ok(Self {
+            hasher: self.hasher.clone(),
+            k: self.k,
+            seed_offset: self.seed_offset,
+            bit_vec,
+            insert_count: self.insert_count.min(other.insert_count),
+            target_error_rate: self.target_error_rate,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
+    }
+
+    /// Create a new `BloomFilter` whose set bits are exactly the bits that
+    /// differ between `self` and `other`, for detecting drift between
+    /// replicas.
+    ///
+    /// The result is a diff of the two bit arrays, not a meaningful
+    /// membership filter: it neither contains the elements of only one of
+    /// the filters, nor excludes those of both. Its insert count is zero. The
+    /// number of set bits of the result is `bit_difference`, which computes
+    /// it without allocating.
+    ///
+    /// Both filters must have the same number of hash functions and the same
+    /// array size, otherwise `BloomError::IncompatibleDimensions` is returned.
+    pub fn symmetric_difference(&self, other: &BloomFilter<T>) -> Result<BloomFilter<T>, BloomError> {
+        self.check_dimensions(other)?;
+
+        let mut bit_vec = self.bit_vec.clone();
+        bit_vec.xor(&other.bit_vec);
+
+        Ok(Self {
+            hasher: self.hasher.clone(),
+            k: self.k,
+            seed_offset: self.seed_offset,
+            bit_vec,
+            insert_count: 0,
+            target_error_rate: self.target_error_rate,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
+    }
+
+    /// Create a new `BloomFilter` with every bit set that is set in a strict
+    /// majority of `filters`.
+    ///
+    /// This is a consensus of replicas of the same filter, tolerating bits
+    /// corrupted in a minority of them. With three replicas, a single
+    /// corrupted replica can neither add nor remove elements. The insert count
+    /// of the result is the median of the insert counts.
+    ///
+    /// Every filter must have the same number of hash functions and the same
+    /// array size, otherwise `BloomError::IncompatibleDimensions` is returned.
+    /// Returns `BloomError::NoFilters` if `filters` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomFilter, Murmur3};
+    ///
+    /// let mut replica = BloomFilter::optimal(Murmur3, 100, 0.01);
+    /// replica.insert(b"Hello");
+    ///
+    /// let mut corrupted = replica.clone();
+    /// corrupted.insert(b"spurious");
+    ///
+    /// let consensus = BloomFilter::majority_vote(&[&replica, &replica, &corrupted]).unwrap();
+    /// assert_eq!(replica, consensus);
+    /// ```
+    pub fn majority_vote(filters: &[&BloomFilter<T>]) -> Result<BloomFilter<T>, BloomError> {
+        let first = filters.first().ok_or(BloomError::NoFilters)?;
+        for filter in filters.iter() {
+            first.check_dimensions(filter)?;
+        }
+
+        // Votes are counted a block at a time, visiting only the set bits of
+        // each block, and only the bits winning the vote are set.
+        let mut bit_vec = BitVec::from_elem(first.bit_vec.len(), false);
+        for i in 0..first.bit_vec.storage().len() {
+            let mut votes = [0_usize; 32];
+            for filter in filters.iter() {
+                for bit in (BlockBits { block: filter.bit_vec.storage()[i], base: 0 }) {
+                    votes[bit] += 1;
+                }
+            }
+
+            let majority = votes
+                .iter()
+                .enumerate()
+                .filter(|&(_, &count)| count * 2 > filters.len())
+                .fold(0_u32, |block, (bit, _)| block | 1 << bit);
+            for index in (BlockBits { block: majority, base: i * 32 }) {
+                bit_vec.set(index, true);
+            }
+        }
+
+        let mut insert_counts: Vec<u64> = filters.iter().map(|filter| filter.insert_count).collect();
+        insert_counts.sort_unstable();
+
+        Ok(Self {
+            hasher: first.hasher.clone(),
+            k: first.k,
+            seed_offset: first.seed_offset,
+            bit_vec,
+            insert_count: insert_counts[(insert_counts.len() - 1) / 2],
+            target_error_rate: first.target_error_rate,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
+    }
+}
+
+/// Creates a `BloomFilter` using `Murmur3`, sized for 1000 elements with an
+/// error rate of 0.01.
+///
+/// This is intended for prototyping, use `BloomFilter::optimal` once the
+/// expected number of elements and acceptable error rate are known.
+impl Default for BloomFilter<Murmur3> {
+    fn default() -> Self {
+        Self::optimal(Murmur3, DEFAULT_MAX_ELEMENTS, DEFAULT_ERROR_RATE)
+    }
+}
+
+/// Inserts every item of an iterator into the `BloomFilter`.
+impl<T: BloomHasher, B: AsRef<[u8]>> Extend<B> for BloomFilter<T> {
+    fn extend<I: IntoIterator<Item = B>>(&mut self, iter: I) {
+        self.insert_iter(iter);
+    }
+}
+
+/// Formats the parameters of the `BloomFilter`, without the bits themselves.
+impl<T: BloomHasher> fmt::Debug for BloomFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BloomFilter")
+            .field("hasher", &any::type_name::<T>())
+            .field("k", &self.k)
+            .field("seed_offset", &self.seed_offset)
+            .field("bits", &self.bit_vec.len())
+            .field("insert_count", &self.insert_count)
+            .field("fill_ratio", &self.fill_ratio())
+            .finish()
+    }
+}
+
+/// Two `BloomFilter`s are equal if they have the same number of hash functions,
+/// seed offset, insert count and bits. The hasher is not compared.
+///
+/// Filters with different configurations are never equal, even if they answer
+/// `contains` identically.
+impl<T> PartialEq for BloomFilter<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.k == other.k
+            && self.seed_offset == other.seed_offset
+            && self.insert_count == other.insert_count
+            && self.bit_vec == other.bit_vec
+    }
+}
+
+impl<T> Eq for BloomFilter<T> {}
+
+/// An iterator over the bit indices of an element, computing the `i`-th index
+/// as `h1 + i * h2`.
+///
+/// Both `h1` and `h2` are derived from a single 64-bit hash, with `h2` being
+/// `h1` with its 32-bit halves swapped.
+pub(crate) struct Probes {
+    h1: u64,
+    h2: u64,
+    i: u32,
+    k: u32,
+    len: usize,
+    shift: Option<u32>,
+}
+
+impl Probes {
+    /// Create an iterator over the `k` indices of `bytes` in an array of `len`
+    /// slots, hashing `bytes` with `seed`.
+    pub(crate) fn new<T: BloomHasher>(
+        hasher: &T,
+        seed: u32,
+        k: u32,
+        len: usize,
+        bytes: &[u8],
+    ) -> Self {
+        let hash = hasher.hash128(seed, bytes);
+        Self::from_hashes(hash as u64, (hash >> 64) as u64, k, len)
+    }
+
+    /// Create an iterator over the `k` indices of an element with the given
+    /// 64-bit hash in an array of `len` slots, using the hash with its 32-bit
+    /// halves swapped as the second base hash.
+    pub(crate) fn from_hash(hash: u64, k: u32, len: usize) -> Self {
+        Self::from_hashes(hash, hash.rotate_left(32), k, len)
+    }
+
+    /// Create an iterator over the `k` indices of an element with the two
+    /// given base hashes in an array of `len` slots.
+    pub(crate) fn from_hashes(h1: u64, h2: u64, k: u32, len: usize) -> Self {
+        // For a power of two length `fastrange` keeps the high bits of the
+        // hash, which a shift computes without the wide multiplication.
+        let shift = if len > 1 && len.is_power_of_two() {
+            Some(64 - len.trailing_zeros())
+        } else {
+            None
+        };
+
+        Self {
+            h1,
+            h2,
+            i: 0,
+            k,
+            len,
+            shift,
+        }
+    }
+}
+
+impl Iterator for Probes {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.i == self.k {
+            return None;
+        }
+
+        let hash = self.h1.wrapping_add((self.i as u64).wrapping_mul(self.h2));
+        self.i += 1;
+        match self.shift {
+            Some(shift) => Some((hash >> shift) as usize),
+            None => Some(fastrange(hash, self.len)),
+        }
+    }
+}
+
+/// A `Hasher` collecting the bytes written to it, used to turn any `Hash`
+/// value into bytes for a `BloomHasher`.
+struct ByteCollector(Vec<u8>);
+
+impl Hasher for ByteCollector {
+    fn finish(&self) -> u64 {
+        unreachable!("ByteCollector only collects bytes")
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+}
+
+/// Returns the bytes written by the `Hash` implementation of `value`.
+fn hash_bytes<H: Hash>(value: &H) -> Vec<u8> {
+    let mut collector = ByteCollector(Vec::new());
+    value.hash(&mut collector);
+    collector.0
+}
+
+/// An iterator over the indices of the set bits of a single block.
+struct BlockBits {
+    block: u32,
+    base: usize,
+}
+
+impl Iterator for BlockBits {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.block == 0 {
+            return None;
+        }
+
+        let bit = self.block.trailing_zeros() as usize;
+        // Clear the lowest set bit
+        self.block &= self.block - 1;
+        Some(self.base + bit)
+    }
+}
+
+/// Map a hash uniformly onto `[0, len)`.
+///
+/// This uses Lemire's fastrange, which avoids both the cost of a division and
+/// the bias towards low indices of reducing the hash modulo `len`.
+#[inline]
+pub(crate) fn fastrange(hash: u64, len: usize) -> usize {
+    ((hash as u128 * len as u128) >> 64) as usize
+}
+
+/// Computes the intersection of two `BloomFilter`s.
+///
+/// # Panics
+///
+/// Panics if the filters have incompatible dimensions. See `BloomFilter::intersect`
+/// for a non-panicking alternative.
+impl<T: BloomHasher + Clone> BitAnd for &BloomFilter<T> {
+    type Output = BloomFilter<T>;
+
+    fn bitand(self, other: Self) -> BloomFilter<T> {
+        self.intersect(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+/// Computes the union of two `BloomFilter`s.
+///
+/// # Panics
+///
+/// Panics if the filters have incompatible dimensions. See `BloomFilter::union`
+/// for a non-panicking alternative.
+impl<T: BloomHasher + Clone> BitOr for &BloomFilter<T> {
+    type Output = BloomFilter<T>;
+
+    fn bitor(self, other: Self) -> BloomFilter<T> {
+        self.union(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+/// Computes the symmetric difference of two `BloomFilter`s.
+///
+/// # Panics
+///
+/// Panics if the filters have incompatible dimensions. See
+/// `BloomFilter::symmetric_difference` for a non-panicking alternative.
+impl<T: BloomHasher + Clone> BitXor for &BloomFilter<T> {
+    type Output = BloomFilter<T>;
+
+    fn bitxor(self, other: Self) -> BloomFilter<T> {
+        self.symmetric_difference(other).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+/// This function computes the false positive rate given n, m, and k.
+#[inline]
+pub(crate) fn false_positive_rate(n: u64, m: u64, k: u32) -> f64 {
+    math::powf(1_f64 - math::exp(-(k as f64) * (n as f64) / (m as f64)), k as f64)
+}
+
+/// The header of the binary format written by `BloomFilter::to_bytes`.
+struct Header {
+    k: u32,
+    seed_offset: u32,
+    insert_count: u64,
+    bit_len: usize,
+    /// The length of the header itself, which depends on the version.
+    len: usize,
+}
+
+impl Header {
+    /// Parse the header of `data`, checking that the length of `data` matches
+    /// the bit length in the header.
+    fn parse(data: &[u8]) -> Result<Self, BloomError> {
+        if data.len() < 5 || &data[0..4] != MAGIC {
+            return Err(BloomError::CorruptData);
+        }
+
+        let mut rest = &data[5..];
+        let header = Self::parse_fields(data[4], &mut rest)?;
+
+        if rest.len() != header.bit_len.div_ceil(8) {
+            return Err(BloomError::CorruptData);
+        }
+
+        Ok(header)
+    }
+
+    /// Returns the length of the fields following the magic bytes and version
+    /// byte in the given version of the format, or `None` if the version is
+    /// unknown.
+    fn fields_len(version: u8) -> Option<usize> {
+        match version {
+            1 => Some(20),
+            2 => Some(HEADER_LEN - 5),
+            _ => None,
+        }
+    }
+
+    /// Parse the fields following the magic bytes and version byte, advancing
+    /// `rest` past them.
+    fn parse_fields(version: u8, rest: &mut &[u8]) -> Result<Self, BloomError> {
+        let fields_len = Self::fields_len(version).ok_or(BloomError::CorruptData)?;
+
+        let k = read_u32(rest)?;
+        let seed_offset = match version {
+            1 => 0,
+            _ => read_u32(rest)?,
+        };
+        let insert_count = read_u64(rest)?;
+        let bit_len = read_u64(rest)?;
+
+        if k == 0 || bit_len == 0 || bit_len > usize::MAX as u64 {
+            return Err(BloomError::CorruptData);
+        }
+
+        Ok(Self {
+            k,
+            seed_offset,
+            insert_count,
+            bit_len: bit_len as usize,
+            len: 5 + fields_len,
+        })
+    }
+}
+
+/// Pack the bits of a `BitVec` into bytes, with bit `i` stored in byte `i / 8`
+/// at position `i % 8` counting from the least significant bit.
+fn pack_bits(bit_vec: &BitVec) -> Vec<u8> {
+    bit_vec.to_bytes().into_iter().map(u8::reverse_bits).collect()
+}
+
+/// Unpack `len` bits from bytes written by `pack_bits`.
+///
+/// `bytes` must contain at least `len` bits.
+fn unpack_bits(bytes: &[u8], len: usize) -> BitVec {
+    let reversed: Vec<u8> = bytes.iter().map(|b| b.reverse_bits()).collect();
+    let mut bit_vec = BitVec::from_bytes(&reversed);
+    bit_vec.truncate(len);
+    bit_vec
+}
+
+/// Read `len` packed bits of the binary format from `reader`, in chunks.
+///
+/// The bit array grows as each chunk is read, so a header claiming more bits
+/// than the data holds ends in `io::ErrorKind::UnexpectedEof` without
+/// allocating the claimed length upfront.
+#[cfg(feature = "std")]
+fn read_bits<R: Read>(reader: &mut R, len: usize) -> io::Result<BitVec> {
+    // Only set bits are written into the bit array, unused bits in the final
+    // byte are ignored, as in `from_bytes`.
+    let mut bit_vec = BitVec::new();
+    let mut buf = vec![0; STREAM_CHUNK_LEN];
+    let mut remaining = len.div_ceil(8);
+    let mut base = 0;
+    while remaining > 0 {
+        let chunk = &mut buf[..remaining.min(STREAM_CHUNK_LEN)];
+        reader.read_exact(chunk)?;
+        remaining -= chunk.len();
+        bit_vec.grow((chunk.len() * 8).min(len - base), false);
+
+        for &byte in chunk.iter() {
+            let bits = BlockBits { block: byte as u32, base };
+            for index in bits.take_while(|&index| index < len) {
+                bit_vec.set(index, true);
+            }
+            base += 8;
+        }
+    }
+
+    Ok(bit_vec)
+}
+
+/// Advance the SplitMix64 generator `state` and return its next output.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Read a little-endian `u32` from the front of `bytes`, advancing it.
+fn read_u32(bytes: &mut &[u8]) -> Result<u32, BloomError> {
+    let mut buf = [0; 4];
+    read_exact(bytes, &mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Read a little-endian `u64` from the front of `bytes`, advancing it.
+fn read_u64(bytes: &mut &[u8]) -> Result<u64, BloomError> {
+    let mut buf = [0; 8];
+    read_exact(bytes, &mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Fill `buf` from the front of `bytes`, advancing it.
+fn read_exact(bytes: &mut &[u8], buf: &mut [u8]) -> Result<(), BloomError> {
+    if bytes.len() < buf.len() {
+        return Err(BloomError::CorruptData);
+    }
+
+    let (head, tail) = bytes.split_at(buf.len());
+    buf.copy_from_slice(head);
+    *bytes = tail;
+    Ok(())
+}
+
+/// Compute the optimal number of hash functions `k` and array size `m` in bits
+/// for the given number of elements and error rate, returning `(k, m)`.
+///
+/// These are the parameters used by `BloomFilter::optimal`, so this can be
+/// used to find out how much memory a filter needs before allocating it.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bloom_filter_rs as bloom_filter;
+///
+/// use bloom_filter::optimal_parameters;
+///
+/// let (k, m) = optimal_parameters(10000, 0.01).unwrap();
+/// assert_eq!(7, k);
+/// assert_eq!(95851, m);
+/// println!("this filter will use {} KB", m.div_ceil(8) / 1024);
+/// ```
+pub fn optimal_parameters(max_elements: u64, error_rate: f64) -> Result<(u32, u64), BloomError> {
+    check_error_rate(error_rate)?;
+
+    if max_elements == 0 {
+        return Err(BloomError::ZeroElements);
+    }
+
+    // Calculate the length of the bit vector, checking it can be allocated
+    let m = check_array_size(optimal_vec_size(max_elements, error_rate), usize::MAX as u64)?;
+
+    // Calculate the number of hash functions to use
+    let k = optimal_hash_functions(m, max_elements);
+
+    Ok((k, m))
+}
+
+/// Returns the number of bits each element costs in an optimally sized filter
+/// with the given error rate, independent of the number of elements.
+///
+/// This is the per-element form of the optimal array size
+/// ```text
+/// -ln(p) / ln(2) ^ 2
+/// ```
+/// so an error rate of 1% costs about 9.6 bits per element. The result is only
+/// meaningful for error rates within `(0, 1)`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bloom_filter_rs as bloom_filter;
+///
+/// use bloom_filter::bits_per_element;
+///
+/// assert!((bits_per_element(0.01) - 9.59).abs() < 0.01);
+/// ```
+pub fn bits_per_element(error_rate: f64) -> f64 {
+    -math::ln(error_rate) / (LN_2 * LN_2)
+}
+
+/// Check whether `key` exists in any of `filters`, stopping at the first
+/// filter that contains it.
+///
+/// This answers membership in the union of the filters without the memory of
+/// building the union, and works across filters of different dimensions. A
+/// false positive of any filter is a false positive of the result, so the
+/// error rate grows with the number of filters.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bloom_filter_rs as bloom_filter;
+///
+/// use bloom_filter::{contains_any_of, BloomFilter, Murmur3};
+///
+/// let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+/// let mut b = BloomFilter::optimal(Murmur3, 1000, 0.01);
+/// a.insert(b"apple");
+/// b.insert(b"banana");
+///
+/// assert!(contains_any_of(&[&a, &b], b"banana"));
+/// ```
+pub fn contains_any_of<T, B>(filters: &[&BloomFilter<T>], key: B) -> bool
+where
+    T: BloomHasher,
+    B: AsRef<[u8]>,
+{
+    let key = key.as_ref();
+    filters.iter().any(|filter| filter.contains(key))
+}
+
+/// Check an array size of `m` bits is below `max_bits`.
+///
+/// Sizes that do not fit in a `usize` would be truncated when allocating the
+/// bit array, and sizes too large for a `u64` saturate to `u64::MAX`.
+pub(crate) fn check_array_size(m: u64, max_bits: u64) -> Result<u64, BloomError> {
+    if m >= max_bits {
+        return Err(BloomError::CapacityTooLarge);
+    }
+
+    Ok(m)
+}
+
+/// Convert a length of `len` bits or slots to a `usize`, returning
+/// `BloomError::CapacityTooLarge` if it does not fit, as on 32-bit targets.
+///
+/// Probe indices are computed in `u64` and are always below the length, so
+/// once the length fits in a `usize` so does every index.
+pub(crate) fn checked_len(len: u64) -> Result<usize, BloomError> {
+    usize::try_from(len).map_err(|_| BloomError::CapacityTooLarge)
+}
+
+/// Check the error rate is within `(0, 1)`, this also rejects NaN.
+pub(crate) fn check_error_rate(error_rate: f64) -> Result<(), BloomError> {
+    if !(error_rate > 0_f64 && error_rate < 1_f64) {
+        return Err(BloomError::InvalidErrorRate(error_rate));
+    }
+
+    Ok(())
+}
+
+/// The optimal number of hash functions is rounded to the nearest integer,
+/// rather than up, since an extra hash function costs time without lowering
+/// the false positive rate.
+#[inline]
+pub(crate) fn optimal_hash_functions(m: u64, n: u64) -> u32 {
+    math::round(m as f64 / n as f64 * LN_2).max(1_f64) as u32
+}
+
+/// Compute the smallest array size for which `k` hash functions stay below
+/// the given error rate for the given number of elements.
+pub(crate) fn array_size_for_k(max_elements: u64, error_rate: f64, k: u32) -> Result<u64, BloomError> {
+    check_error_rate(error_rate)?;
+
+    if max_elements == 0 {
+        return Err(BloomError::ZeroElements);
+    }
+    if k == 0 {
+        return Err(BloomError::ZeroHashFunctions);
+    }
+
+    check_array_size(vec_size_for_k(max_elements, error_rate, k), usize::MAX as u64)
+}
+
+/// The smallest array size for which `k` hash functions reach an error rate of
+/// `p` after `n` elements, saturating to `u64::MAX` if it is not finite.
+fn vec_size_for_k(n: u64, p: f64, k: u32) -> u64 {
+    let k = k as f64;
+    math::ceil(-(k * n as f64) / math::ln(1_f64 - math::powf(p, 1_f64 / k))) as u64
+}
+
+#[inline]
+fn optimal_vec_size(n: u64, p: f64) -> u64 {
+    math::ceil(-(n as f64 * math::ln(p)) / (LN_2 * LN_2)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use std::fs::File;
+    #[cfg(feature = "std")]
+    use std::io::{BufReader, BufRead, Cursor};
+    use super::*;
+    use hash::{Fnv1a, SipHash13};
+
+    #[test]
+    fn test_optimal_hash_functions() {
+        assert_eq!(1, optimal_hash_functions(1, 10));
+        assert_eq!(7, optimal_hash_functions(95851, 10000));
+        assert_eq!(7, optimal_hash_functions(9586, 1000));
+        assert_eq!(6, optimal_hash_functions(90000, 10000));
+        assert_eq!(1, optimal_hash_functions(1000, 10000));
+    }
+
+    #[test]
+    fn test_optimal_with_k() {
+        for &k in [1_u32, 3, 7, 12].iter() {
+            let bloom_filter = BloomFilter::optimal_with_k(Murmur3, 1000, 0.01, k).unwrap();
+            let m = bloom_filter.capacity_bits();
+            assert_eq!(k, bloom_filter.num_hashes());
+
+            // The array size is the smallest meeting the target.
+            assert!(false_positive_rate(1000, m, k) <= 0.01);
+            assert!(false_positive_rate(1000, m - 1, k) > 0.01);
+        }
+
+        // The optimal number of hash functions gives the optimal size.
+        let bloom_filter = BloomFilter::optimal_with_k(Murmur3, 1000, 0.01, 7).unwrap();
+        assert!((bloom_filter.capacity_bits() as i64 - 9586).abs() < 50);
+    }
+
+    #[test]
+    fn test_optimal_with_k_invalid() {
+        assert_eq!(
+            Some(BloomError::ZeroHashFunctions),
+            BloomFilter::optimal_with_k(Murmur3, 1000, 0.01, 0).err()
+        );
+        assert_eq!(
+            Some(BloomError::ZeroElements),
+            BloomFilter::optimal_with_k(Murmur3, 0, 0.01, 7).err()
+        );
+        assert_eq!(
+            Some(BloomError::CapacityTooLarge),
+            BloomFilter::optimal_with_k(Murmur3, u64::MAX / 10, 0.01, 1).err()
+        );
+    }
+
+    #[test]
+    fn test_optimal_vec_size() {
+        assert_eq!(95851, optimal_vec_size(10000, 0.01));
+        assert_eq!(9586, optimal_vec_size(1000, 0.01));
+    }
+
+    #[test]
+    fn test_parameters() {
+        let bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01).with_seed_offset(3);
+        let parameters = bloom_filter.parameters();
+        assert_eq!(7, parameters.num_hashes());
+        assert_eq!(bloom_filter.capacity_bits(), parameters.capacity_bits());
+        assert_eq!(3, parameters.seed_offset());
+        assert!(parameters.hasher().ends_with("Murmur3"));
+
+        let m = bloom_filter.capacity_bits();
+        let compatible = BloomFilter::new(Murmur3, 7, m).with_seed_offset(3);
+        assert!(parameters.is_compatible(&compatible.parameters()));
+        assert!(bloom_filter.is_compatible_with(&compatible));
+
+        let incompatible = [
+            BloomFilter::optimal(Fnv1a, 1000, 0.01).with_seed_offset(3).parameters(),
+            BloomFilter::optimal(Murmur3, 1000, 0.01).parameters(),
+            BloomFilter::optimal(Murmur3, 2000, 0.01).with_seed_offset(3).parameters(),
+            BloomFilter::new(Murmur3, 8, m).with_seed_offset(3).parameters(),
+        ];
+        for other in incompatible.iter() {
+            assert!(!parameters.is_compatible(other));
+        }
+    }
+
+    #[test]
+    fn test_contains_any_of() {
+        let words: Vec<String> = (0..400).map(|i| format!("word{}", i)).collect();
+        let mut shards = [
+            BloomFilter::optimal(Murmur3, 100, 0.01),
+            BloomFilter::optimal(Murmur3, 100, 0.01),
+            BloomFilter::new(Murmur3, 5, 2000),
+        ];
+        for (shard, chunk) in shards.iter_mut().zip(words.chunks(100)) {
+            shard.insert_all(chunk);
+        }
+        let filters: Vec<&BloomFilter<Murmur3>> = shards.iter().collect();
+
+        for word in words[..300].iter() {
+            assert!(contains_any_of(&filters, word));
+        }
+        for word in words[300..].iter() {
+            let expected = shards.iter().any(|shard| shard.contains(word));
+            assert_eq!(expected, contains_any_of(&filters, word));
+        }
+        assert!(!contains_any_of::<Murmur3, _>(&[], "word0"));
+    }
+
+    #[test]
+    fn test_bits_per_element() {
+        assert_eq!(959, math::round(bits_per_element(0.01) * 100_f64) as u64);
+        assert!(bits_per_element(0.001) > bits_per_element(0.01));
+
+        for &(n, p) in [(1000, 0.01), (10000, 0.01), (12345, 0.001), (1, 0.5)].iter() {
+            let expected = math::ceil(n as f64 * bits_per_element(p)) as u64;
+            assert!((optimal_vec_size(n, p) as i64 - expected as i64).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn test_optimal_parameters() {
+        assert_eq!(Ok((7, 95851)), optimal_parameters(10000, 0.01));
+        assert_eq!(Ok((7, 9586)), optimal_parameters(1000, 0.01));
+        assert_eq!(Err(BloomError::ZeroElements), optimal_parameters(0, 0.01));
+        assert_eq!(Err(BloomError::InvalidErrorRate(0_f64)), optimal_parameters(1000, 0_f64));
+    }
+
+    #[test]
+    fn test_capacity_too_large() {
+        assert_eq!(
+            Some(BloomError::CapacityTooLarge),
+            BloomFilter::try_optimal(Murmur3, u64::MAX, 1e-300).err()
+        );
+
+        // Simulate a 32-bit usize
+        let m = optimal_vec_size(1_000_000_000, 0.01);
+        assert_eq!(Err(BloomError::CapacityTooLarge), check_array_size(m, u32::MAX as u64));
+        assert_eq!(Ok(9586), check_array_size(9586, u32::MAX as u64));
+    }
+
+    #[test]
+    fn test_checked_len() {
+        assert_eq!(Ok(1000), checked_len(1000));
+        assert_eq!(Ok(usize::MAX), checked_len(usize::MAX as u64));
+        assert_eq!(1000, BloomFilter::try_new(Murmur3, 7, 1000).unwrap().capacity_bits());
+        assert_eq!(1, BloomFilter::try_new(Murmur3, 7, 0).unwrap().capacity_bits());
+
+        // The largest index of the largest array still fits in a `usize`.
+        assert_eq!(usize::MAX - 1, fastrange(u64::MAX, usize::MAX));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn test_checked_len_32_bit() {
+        let len = u32::MAX as u64 + 1;
+        assert_eq!(Err(BloomError::CapacityTooLarge), checked_len(len));
+        assert_eq!(
+            Some(BloomError::CapacityTooLarge),
+            BloomFilter::try_new(Murmur3, 7, len).err()
+        );
+        assert_eq!(
+            Some(BloomError::CapacityTooLarge),
+            BloomFilter::try_optimal(Murmur3, 1_000_000_000, 0.01).err()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_error_rate_too_low() {
+        BloomFilter::optimal(Murmur3, 10000, 0_f64);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_error_rate_too_high() {
+        BloomFilter::optimal(Murmur3, 10000, 1_f64);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_elements() {
+        BloomFilter::optimal(Murmur3, 0, 0.01);
+    }
+
+    #[test]
+    fn test_try_optimal() {
+        assert!(BloomFilter::try_optimal(Murmur3, 10000, 0.01).is_ok());
+
+        match BloomFilter::try_optimal(Murmur3, 10000, f64::NAN) {
+            Err(BloomError::InvalidErrorRate(rate)) => assert!(rate.is_nan()),
+            _ => panic!("expected an invalid error rate"),
+        }
+
+        assert_eq!(
+            Some(BloomError::InvalidErrorRate(0_f64)),
+            BloomFilter::try_optimal(Murmur3, 10000, 0_f64).err()
+        );
+        assert_eq!(
+            Some(BloomError::InvalidErrorRate(1_f64)),
+            BloomFilter::try_optimal(Murmur3, 10000, 1_f64).err()
+        );
+        assert_eq!(
+            Some(BloomError::ZeroElements),
+            BloomFilter::try_optimal(Murmur3, 0, 0.01).err()
+        );
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        assert_eq!(0_f64, false_positive_rate(0, 95851, 7));
+        assert!((false_positive_rate(10000, 95851, 7) - 0.01).abs() < 0.001);
+        assert!((false_positive_rate(1000, 9586, 7) - 0.01).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_false_positive_rate_at_capacity() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        assert_eq!(0_f64, bloom_filter.false_positive_rate());
+
+        for i in 0..1000_u32 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+
+        assert!((bloom_filter.false_positive_rate() - 0.01).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_hasher() {
+        let bloom_filter = BloomFilter::optimal(SipHash13::new(1, 2), 100, 0.01);
+        assert_eq!(SipHash13::new(1, 2).hash(0, b"hello"), bloom_filter.hasher().hash(0, b"hello"));
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        let bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01).with_seed_offset(3);
+        let (k, m) = (bloom_filter.num_hashes(), bloom_filter.capacity_bits());
+
+        let mut bloom_filter = bloom_filter.with_hasher(Fnv1a);
+        assert_eq!(k, bloom_filter.num_hashes());
+        assert_eq!(m, bloom_filter.capacity_bits());
+        assert_eq!(3, bloom_filter.seed_offset());
+
+        bloom_filter.insert(b"hello");
+        let mut expected = BloomFilter::optimal(Fnv1a, 100, 0.01).with_seed_offset(3);
+        expected.insert(b"hello");
+        assert_eq!(expected, bloom_filter);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_hasher_non_empty() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        bloom_filter.insert(b"hello");
+        bloom_filter.with_hasher(Fnv1a);
+    }
+
+    #[test]
+    fn test_distinct_probe_count() {
+        let bloom_filter = BloomFilter::new(Murmur3, 7, 3);
+        assert!(bloom_filter.distinct_probe_count(b"hello") <= 3);
+
+        let mut bloom_filter = BloomFilter::new(Murmur3, 7, 1_000_000);
+        assert_eq!(7, bloom_filter.distinct_probe_count(b"hello"));
+
+        bloom_filter.insert(b"hello");
+        assert_eq!(7, bloom_filter.count_ones());
+    }
+
+    #[test]
+    fn test_explain_contains() {
+        let mut bloom_filter = BloomFilter::new(Murmur3, 7, 1000);
+        bloom_filter.insert(b"hello");
+
+        let h1 = Murmur3.hash64(0, b"hello");
+        let h2 = h1.rotate_left(32);
+        let expected: Vec<usize> = (0..7_u64)
+            .map(|i| fastrange(h1.wrapping_add(i.wrapping_mul(h2)), 1000))
+            .collect();
+
+        let report = bloom_filter.explain_contains(b"hello");
+        assert_eq!(expected, report.indices().collect::<Vec<usize>>());
+        assert!(report.probes().iter().all(|&(_, set)| set));
+        assert!(report.is_hit());
+
+        for word in ["apple", "banana", "cherry"].iter() {
+            let report = bloom_filter.explain_contains(word);
+            assert_eq!(bloom_filter.contains(word), report.is_hit());
+            for &(index, set) in report.probes() {
+                assert_eq!(bloom_filter.bit_vec[index], set);
+            }
+        }
+    }
+
+    #[test]
+    fn test_capacity_for_error() {
+        let bloom_filter = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        let capacity = bloom_filter.capacity_for_error(0.01);
+        assert!((capacity as i64 - 10000).abs() < 100, "capacity of {}", capacity);
+
+        for target in [0.001, 0.01, 0.05].iter() {
+            let capacity = bloom_filter.capacity_for_error(*target);
+            assert!(bloom_filter.projected_false_positive_rate(capacity) <= *target);
+            assert!(bloom_filter.projected_false_positive_rate(capacity + 1) > *target);
+        }
+
+        assert_eq!(0, bloom_filter.capacity_for_error(0_f64));
+        assert_eq!(0, bloom_filter.capacity_for_error(1.5));
+    }
+
+    #[test]
+    fn test_insert_integers() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        assert!(bloom_filter.insert_u64(42));
+        assert!(bloom_filter.contains_u64(42));
+        assert!(!bloom_filter.contains_u64(43));
+        assert!(bloom_filter.contains_i64(42));
+
+        bloom_filter.insert_i64(-1);
+        assert!(bloom_filter.contains_i64(-1));
+        assert!(bloom_filter.contains_u64(u64::MAX));
+
+        // The encoding is little-endian regardless of the platform.
+        let mut expected = BloomFilter::optimal(Murmur3, 100, 0.01);
+        expected.insert([42, 0, 0, 0, 0, 0, 0, 0]);
+        expected.insert([0xff; 8]);
+        assert_eq!(expected, bloom_filter);
+    }
+
+    #[test]
+    fn test_u64_hash() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01).with_seed_offset(3);
+        let hash = |i: u32| Murmur3.hash64(3, &i.to_le_bytes());
+
+        for i in 0..500_u32 {
+            bloom_filter.insert_u64_hash(hash(i));
+        }
+        for i in 0..2000_u32 {
+            assert_eq!(bloom_filter.contains(i.to_le_bytes()), bloom_filter.contains_u64_hash(hash(i)));
+        }
+
+        let mut expected = BloomFilter::optimal(Murmur3, 1000, 0.01).with_seed_offset(3);
+        for i in 0..500_u32 {
+            expected.insert(i.to_le_bytes());
+        }
+        assert_eq!(expected, bloom_filter);
+    }
+
+    #[test]
+    fn test_precomputed() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01).with_seed_offset(3);
+        let hashes = |i: u32| {
+            let h1 = Murmur3.hash64(3, &i.to_le_bytes());
+            (h1, h1.rotate_left(32))
+        };
+
+        for i in 0..500_u32 {
+            let (h1, h2) = hashes(i);
+            bloom_filter.insert_precomputed(h1, h2);
+        }
+        for i in 0..2000_u32 {
+            let (h1, h2) = hashes(i);
+            assert_eq!(bloom_filter.contains(i.to_le_bytes()), bloom_filter.contains_precomputed(h1, h2));
+        }
+
+        let mut expected = BloomFilter::optimal(Murmur3, 1000, 0.01).with_seed_offset(3);
+        for i in 0..500_u32 {
+            expected.insert(i.to_le_bytes());
+        }
+        assert_eq!(expected, bloom_filter);
+    }
+
+    #[test]
+    fn test_health() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        assert_eq!(0.01, bloom_filter.target_error_rate());
+        assert_eq!(FilterHealth::Ok, bloom_filter.health());
+
+        let mut transitions = vec![bloom_filter.health()];
+        for i in 0..10000_u64 {
+            bloom_filter.insert_u64(i);
+
+            let health = bloom_filter.health();
+            let previous = *transitions.last().unwrap();
+            if mem::discriminant(&health) != mem::discriminant(&previous) {
+                transitions.push(health);
+            }
+            // The realized fill ratio varies around its expectation, so the
+            // filter may degrade slightly before reaching its capacity.
+            if i < 900 {
+                assert_eq!(FilterHealth::Ok, health);
+            }
+        }
+
+        assert_eq!(3, transitions.len());
+        match transitions[1] {
+            FilterHealth::Degraded(rate) => assert!(rate > 0.01 && rate < 0.02),
+            health => panic!("unexpected health {:?}", health),
+        }
+        assert_eq!(FilterHealth::Saturated, transitions[2]);
+    }
+
+    #[test]
+    fn test_target_error_rate() {
+        assert_eq!(0.001, BloomFilter::optimal_pow2(Murmur3, 100, 0.001).target_error_rate());
+        assert_eq!(0.05, BloomFilter::optimal_with_k(Murmur3, 100, 0.05, 3).unwrap().target_error_rate());
+
+        let bloom_filter = BloomFilter::new(Murmur3, 7, 9586);
+        assert_eq!(DEFAULT_ERROR_RATE, bloom_filter.target_error_rate());
+        assert_eq!(0.1, bloom_filter.with_target_error_rate(0.1).target_error_rate());
+    }
+
+    #[test]
+    fn test_optimal_pow2() {
+        let words: Vec<String> = (0..10000).map(|i| format!("word{}", i)).collect();
+        let mut bloom_filter = BloomFilter::optimal_pow2(Murmur3, 10000, 0.01);
+        assert_eq!(131072, bloom_filter.capacity_bits());
+        assert_eq!(9, bloom_filter.num_hashes());
+
+        bloom_filter.insert_all(&words);
+        for word in words.iter() {
+            assert!(bloom_filter.contains(word));
+        }
+        assert_eq!(
+            Some(BloomError::InvalidErrorRate(1.5)),
+            BloomFilter::try_optimal_pow2(Murmur3, 1000, 1.5).err()
+        );
+    }
+
+    #[test]
+    fn test_pow2_probes_match_fastrange() {
+        for &len in [1, 2, 1024, 1 << 20].iter() {
+            let h2 = 0x0123_4567_89ab_cdef_u64.rotate_left(32);
+            let probes = Probes::from_hashes(0x0123_4567_89ab_cdef, h2, 7, len);
+            let expected: Vec<usize> = (0..7_u64)
+                .map(|i| fastrange(0x0123_4567_89ab_cdef_u64.wrapping_add(i.wrapping_mul(h2)), len))
+                .collect();
+            assert_eq!(expected, probes.collect::<Vec<usize>>());
+        }
+    }
+
+    #[test]
+    fn test_optimal_false_positive_rate() {
+        for &(n, p) in [(1000, 0.01), (10000, 0.01), (100000, 0.001)].iter() {
+            let bloom_filter = BloomFilter::optimal(Murmur3, n, p);
+            let rate = bloom_filter.optimal_false_positive_rate(n);
+
+            assert!(rate != p);
+            assert!((rate - p).abs() / p < 0.05, "{} vs {}", rate, p);
+        }
+    }
+
+    #[test]
+    fn test_projected_false_positive_rate() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        for i in 0..500_u32 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+        assert_eq!(
+            bloom_filter.false_positive_rate(),
+            bloom_filter.projected_false_positive_rate(500)
+        );
+
+        let mut previous = 0_f64;
+        for n in (1000..20000).step_by(1000) {
+            let rate = bloom_filter.projected_false_positive_rate(n);
+            assert!(rate > previous);
+            previous = rate;
+        }
+        assert!((bloom_filter.projected_false_positive_rate(1000) - 0.01).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_with_memory() {
+        let bloom_filter = BloomFilter::with_memory(Murmur3, 1000, 1000);
+        assert_eq!(8000, bloom_filter.capacity_bits());
+        assert_eq!(optimal_hash_functions(8000, 1000), bloom_filter.num_hashes());
+        assert_eq!(6, bloom_filter.num_hashes());
+
+        // Any other number of hash functions gives a higher error rate.
+        let rate = bloom_filter.projected_false_positive_rate(1000);
+        assert!(rate < false_positive_rate(1000, 8000, 5));
+        assert!(rate < false_positive_rate(1000, 8000, 7));
+    }
+
+    #[test]
+    fn test_warm() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1_000_000, 0.01);
+        for i in 0..1000_u32 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+        let before = bloom_filter.clone();
+
+        bloom_filter.warm();
+        assert_eq!(before, bloom_filter);
+        for i in 0..1000_u32 {
+            assert!(bloom_filter.contains(i.to_le_bytes()));
+        }
+
+        BloomFilter::new(Murmur3, 1, 1).warm();
+    }
+
+    #[test]
+    fn test_zero_array_size() {
+        let mut bloom_filter = BloomFilter::new(Murmur3, 3, 0);
+        assert!(!bloom_filter.contains("hello"));
+
+        bloom_filter.insert(b"hello");
+        assert!(bloom_filter.contains("hello"));
+    }
+
+    #[test]
+    fn test_zero_hash_functions() {
+        let mut bloom_filter = BloomFilter::new(Murmur3, 0, 100);
+        assert!(!bloom_filter.contains("hello"));
+
+        bloom_filter.insert(b"hello");
+        assert!(bloom_filter.contains("hello"));
+    }
+
+    #[test]
+    fn test_union() {
+        let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+        let mut b = BloomFilter::optimal(Murmur3, 100, 0.01);
+        a.insert_all(&["apple", "banana"]);
+        b.insert_all(&["cherry"]);
+
+        let union = a.union(&b).unwrap();
+        for word in ["apple", "banana", "cherry"].iter() {
+            assert!(union.contains(word));
+        }
+        assert_eq!(3, union.insert_count);
+
+        let union = &a | &b;
+        for word in ["apple", "banana", "cherry"].iter() {
+            assert!(union.contains(word));
+        }
+    }
+
+    #[test]
+    fn test_set_insert_count() {
+        let words: Vec<String> = (0..150).map(|i| format!("word{}", i)).collect();
+
+        let mut a = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        let mut b = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        a.insert_all(&words[..100]);
+        b.insert_all(&words[50..]);
+
+        let mut union = a.union(&b).unwrap();
+        assert_eq!(200, union.len());
+
+        let estimate = union.estimate_cardinality().round() as u64;
+        assert!((145..155).contains(&estimate));
+        union.set_insert_count(estimate);
+        assert_eq!(estimate, union.len());
+        assert_eq!(union.projected_false_positive_rate(estimate), union.false_positive_rate());
+        assert!(union.false_positive_rate() < union.projected_false_positive_rate(200));
+        assert!(union.contains_all(&words));
+    }
+
+    #[test]
+    fn test_is_compatible_with() {
+        let a = BloomFilter::new(Murmur3, 3, 100);
+        assert!(a.is_compatible_with(&BloomFilter::new(Murmur3, 3, 100)));
+        assert!(!a.is_compatible_with(&BloomFilter::new(Murmur3, 4, 100)));
+        assert!(!a.is_compatible_with(&BloomFilter::new(Murmur3, 3, 101)));
+        assert!(!a.is_compatible_with(&BloomFilter::new(Murmur3, 3, 100).with_seed_offset(1)));
+
+        let b = BloomFilter::new(Murmur3, 4, 100);
+        assert_eq!(a.is_compatible_with(&b), a.union(&b).is_ok());
+    }
+
+    #[test]
+    fn test_union_incompatible() {
+        let a = BloomFilter::new(Murmur3, 3, 100);
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.union(&BloomFilter::new(Murmur3, 4, 100)).err()
+        );
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.union(&BloomFilter::new(Murmur3, 3, 101)).err()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn test_stats() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        assert_eq!(FilterStats::default(), bloom_filter.stats());
+        assert_eq!(0_f64, bloom_filter.stats().already_set_ratio());
+
+        for i in 0..500_u32 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+        let stats = bloom_filter.stats();
+        assert_eq!(bloom_filter.count_ones(), stats.bits_set());
+        assert_eq!(500 * bloom_filter.num_hashes() as u64, stats.bits_set() + stats.already_set());
+        let fresh_ratio = stats.already_set_ratio();
+        assert!(fresh_ratio < 0.2);
+
+        // Every probe of a duplicate hits an already set bit.
+        for i in 0..500_u32 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+        assert_eq!(stats.bits_set(), bloom_filter.stats().bits_set());
+        assert!(bloom_filter.stats().already_set_ratio() > 0.5);
+        assert!(bloom_filter.stats().already_set_ratio() > fresh_ratio);
+
+        bloom_filter.clear();
+        assert_eq!(FilterStats::default(), bloom_filter.stats());
+    }
+
+    #[test]
+    fn test_majority_vote() {
+        let words: Vec<String> = (0..200).map(|i| format!("word{}", i)).collect();
+        let mut replica = BloomFilter::optimal(Murmur3, 100, 0.01);
+        replica.insert_all(&words[..100]);
+
+        let mut corrupted = replica.clone();
+        corrupted.insert_all(&words[100..]);
+        assert!(corrupted.count_ones() > replica.count_ones());
+
+        let consensus = BloomFilter::majority_vote(&[&replica, &corrupted, &replica]).unwrap();
+        assert_eq!(replica, consensus);
+        assert_eq!(100, consensus.len());
+        for word in words[100..].iter() {
+            assert_eq!(replica.contains(word), consensus.contains(word));
+        }
+
+        // A tie is not a majority.
+        let consensus = BloomFilter::majority_vote(&[&replica, &corrupted]).unwrap();
+        assert_eq!(replica.bit_vec, consensus.bit_vec);
+
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            BloomFilter::majority_vote(&[&replica, &BloomFilter::new(Murmur3, 3, 100)]).err()
+        );
+        assert_eq!(Some(BloomError::NoFilters), BloomFilter::<Murmur3>::majority_vote(&[]).err());
+    }
+
+    #[test]
+    fn test_majority_vote_matches_bitwise() {
+        let words: Vec<String> = (0..500).map(|i| format!("word{}", i)).collect();
+        let replicas: Vec<BloomFilter<Murmur3>> = (0..5)
+            .map(|i| {
+                let mut replica = BloomFilter::new(Murmur3, 3, 1001);
+                replica.insert_all(&words[i * 50..i * 50 + 250]);
+                replica
+            })
+            .collect();
+        let replicas: Vec<&BloomFilter<Murmur3>> = replicas.iter().collect();
+
+        let consensus = BloomFilter::majority_vote(&replicas).unwrap();
+        for index in 0..1001 {
+            let votes = replicas.iter().filter(|replica| replica.bit_vec[index]).count();
+            assert_eq!(votes >= 3, consensus.bit_vec[index]);
+        }
+    }
+
+    #[test]
+    fn test_union_in_place() {
+        let words: Vec<String> = (0..400).map(|i| format!("word{}", i)).collect();
+        let shards: Vec<BloomFilter<Murmur3>> = words
+            .chunks(100)
+            .map(|chunk| {
+                let mut shard = BloomFilter::optimal(Murmur3, 400, 0.01);
+                shard.insert_all(chunk);
+                shard
+            })
+            .collect();
+
+        let mut accumulator = BloomFilter::optimal(Murmur3, 400, 0.01);
+        let mut expected = BloomFilter::optimal(Murmur3, 400, 0.01);
+        for shard in shards.iter() {
+            accumulator.union_in_place(shard).unwrap();
+            expected = expected.union(shard).unwrap();
+        }
+
+        assert_eq!(expected, accumulator);
+        assert_eq!(400, accumulator.len());
+        assert!(accumulator.contains_all(&words));
+
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            accumulator.union_in_place(&BloomFilter::new(Murmur3, 3, 100)).err()
+        );
+        assert_eq!(expected, accumulator);
+    }
+
+    #[test]
+    fn test_intersect() {
+        let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+        let mut b = BloomFilter::optimal(Murmur3, 100, 0.01);
+        a.insert_all(&["apple", "banana", "cherry"]);
+        b.insert_all(&["banana", "cherry", "durian", "elderberry"]);
+
+        let intersection = a.intersect(&b).unwrap();
+        assert!(intersection.contains("banana"));
+        assert!(intersection.contains("cherry"));
+        assert_eq!(3, intersection.insert_count);
+
+        let intersection = &a & &b;
+        assert!(intersection.contains("banana"));
+        assert!(intersection.contains("cherry"));
+    }
+
+    #[test]
+    fn test_intersect_disjoint() {
+        let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+        let mut b = BloomFilter::optimal(Murmur3, 100, 0.01);
+        a.insert_all(&["apple", "banana"]);
+        b.insert_all(&["cherry", "durian"]);
+
+        let intersection = a.intersect(&b).unwrap();
+        for word in ["apple", "banana", "cherry", "durian"].iter() {
+            assert!(!intersection.contains(word));
+        }
+
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.intersect(&BloomFilter::new(Murmur3, 3, 100)).err()
+        );
+    }
+
+    #[test]
+    fn test_merge_rescaled() {
+        let small_words = ["apple", "banana"];
+        let large_words = ["cherry", "durian", "elderberry"];
+
+        let mut small = BloomFilter::new(Murmur3, 5, 1000);
+        let mut large = BloomFilter::new(Murmur3, 5, 3000);
+        small.insert_all(&small_words);
+        large.insert_all(&large_words);
+        small.merge_rescaled(&large).unwrap();
+
+        let mut expected = BloomFilter::new(Murmur3, 5, 1000);
+        expected.insert_all(&small_words);
+        expected.insert_all(&large_words);
+        assert_eq!(expected, small);
+    }
+
+    #[test]
+    fn test_merge_rescaled_incompatible() {
+        let mut a = BloomFilter::new(Murmur3, 5, 1000);
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.merge_rescaled(&BloomFilter::new(Murmur3, 5, 1500)).err()
+        );
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.merge_rescaled(&BloomFilter::new(Murmur3, 5, 500)).err()
+        );
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.merge_rescaled(&BloomFilter::new(Murmur3, 4, 2000)).err()
+        );
+    }
+
+    #[test]
+    fn test_is_subset() {
+        let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+        let mut b = BloomFilter::optimal(Murmur3, 100, 0.01);
+        a.insert_all(&["apple", "banana"]);
+        b.insert_all(&["apple", "banana", "cherry"]);
+
+        assert_eq!(Ok(true), a.is_subset(&b));
+        assert_eq!(Ok(false), b.is_subset(&a));
+        assert_eq!(Ok(true), a.is_subset(&a));
+        assert_eq!(Ok(true), BloomFilter::optimal(Murmur3, 100, 0.01).is_subset(&a));
+
+        a.insert(b"durian");
+        assert_eq!(Ok(false), a.is_subset(&b));
+
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.is_subset(&BloomFilter::new(Murmur3, 3, 100)).err()
+        );
     }
 
     #[test]
-    fn test_optimal_vec_size() {
-        assert_eq!(95851, optimal_vec_size(10000, 0.01));
-        assert_eq!(9586, optimal_vec_size(1000, 0.01));
+    fn test_symmetric_difference() {
+        let words: Vec<String> = (0..150).map(|i| format!("word{}", i)).collect();
+        let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+        let mut b = BloomFilter::optimal(Murmur3, 100, 0.01);
+        a.insert_all(&words[..100]);
+        b.insert_all(&words[50..]);
+
+        let difference = a.symmetric_difference(&b).unwrap();
+        assert_eq!(a.bit_difference(&b), Ok(difference.count_ones()));
+        assert_eq!(0, difference.len());
+        for index in 0..difference.bit_vec.len() {
+            assert_eq!(a.bit_vec[index] != b.bit_vec[index], difference.bit_vec[index]);
+        }
+
+        assert_eq!(difference, &a ^ &b);
+        assert_eq!(0, a.symmetric_difference(&a).unwrap().count_ones());
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.symmetric_difference(&BloomFilter::new(Murmur3, 3, 100)).err()
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn test_error_rate_too_low() {
-        BloomFilter::optimal(Murmur3, 10000, 0_f64);
+    fn test_bit_difference() {
+        let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+        a.insert_all(&["apple", "banana"]);
+
+        let mut b = a.clone();
+        assert_eq!(Ok(0), a.bit_difference(&b));
+
+        let before = b.count_ones();
+        b.insert(b"cherry");
+        let flipped = b.count_ones() - before;
+
+        assert!(flipped > 0 && flipped <= b.num_hashes() as u64);
+        assert_eq!(Ok(flipped), a.bit_difference(&b));
+        assert_eq!(Ok(flipped), b.bit_difference(&a));
+
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.bit_difference(&BloomFilter::new(Murmur3, 3, 100)).err()
+        );
+    }
+
+    #[test]
+    fn test_estimate_jaccard() {
+        let mut a = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        let mut b = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        assert_eq!(Ok(1_f64), a.estimate_jaccard(&b));
+
+        // The sets overlap by 2000 of their 8000 distinct elements.
+        for i in 0..5000_u32 {
+            a.insert(i.to_le_bytes());
+        }
+        for i in 3000..8000_u32 {
+            b.insert(i.to_le_bytes());
+        }
+
+        let estimate = a.estimate_jaccard(&b).unwrap();
+        assert!((estimate - 0.25).abs() < 0.02, "estimate of {}", estimate);
+        assert_eq!(Ok(1_f64), a.estimate_jaccard(&a));
+
+        let mut c = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        for i in 10000..15000_u32 {
+            c.insert(i.to_le_bytes());
+        }
+        assert!(a.estimate_jaccard(&c).unwrap() < 0.01);
+
+        assert_eq!(
+            Some(BloomError::IncompatibleDimensions),
+            a.estimate_jaccard(&BloomFilter::new(Murmur3, 3, 100)).err()
+        );
+    }
+
+    #[test]
+    fn test_check_and_insert() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        for i in 0..1000_u32 {
+            let present = bloom_filter.contains(i.to_le_bytes());
+            assert_eq!(present, bloom_filter.check_and_insert(i.to_le_bytes()));
+            assert!(bloom_filter.check_and_insert(i.to_le_bytes()));
+        }
+        assert_eq!(2000, bloom_filter.len());
+    }
+
+    #[test]
+    fn test_insert_as_ref() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        let word = String::from("apple");
+        bloom_filter.insert(&word);
+        bloom_filter.insert("banana");
+        bloom_filter.insert(vec![1_u8, 2, 3]);
+        bloom_filter.insert(word);
+
+        assert!(bloom_filter.contains("apple"));
+        assert!(bloom_filter.contains("banana"));
+        assert!(bloom_filter.contains([1_u8, 2, 3]));
+        assert_eq!(4, bloom_filter.len());
+    }
+
+    #[test]
+    fn test_insert_new() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        assert!(bloom_filter.insert(b"apple"));
+        assert!(!bloom_filter.insert(b"apple"));
+        assert!(bloom_filter.insert(b"banana"));
+        assert_eq!(3, bloom_filter.len());
+    }
+
+    #[test]
+    fn test_contains_all_any() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        bloom_filter.insert_all(&["apple", "banana"]);
+
+        assert!(bloom_filter.contains_all(&["apple", "banana"]));
+        assert!(!bloom_filter.contains_all(&["apple", "cherry"]));
+        assert!(bloom_filter.contains_all::<&str>(&[]));
+
+        assert!(bloom_filter.contains_any(&["cherry", "banana"]));
+        assert!(!bloom_filter.contains_any(&["cherry", "durian"]));
+        assert!(!bloom_filter.contains_any::<&str>(&[]));
+    }
+
+    #[test]
+    fn test_extend() {
+        let words = ["apple", "banana", "cherry", "durian"];
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+
+        bloom_filter.extend(words.iter().filter(|w| w.len() > 5).map(|w| w.to_uppercase()));
+
+        assert_eq!(3, bloom_filter.len());
+        assert!(bloom_filter.contains("BANANA"));
+        assert!(bloom_filter.contains("CHERRY"));
+        assert!(bloom_filter.contains("DURIAN"));
+        assert!(!bloom_filter.contains("APPLE"));
+    }
+
+    #[test]
+    fn test_clear() {
+        let words = ["apple", "banana", "cherry"];
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        bloom_filter.insert_all(&words);
+
+        bloom_filter.clear();
+
+        for word in words.iter() {
+            assert!(!bloom_filter.contains(word));
+        }
+        assert_eq!(0_f64, bloom_filter.false_positive_rate());
+        assert_eq!(optimal_vec_size(100, 0.01) as usize, bloom_filter.bit_vec.len());
+    }
+
+    #[test]
+    fn test_rebuild_from() {
+        let words: Vec<String> = (0..100).map(|i| format!("word{}", i)).collect();
+
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100000, 0.01);
+        bloom_filter.insert_all(&words);
+        assert!((bloom_filter.estimate_cardinality() - 100_f64).abs() < 5_f64);
+
+        let rebuilt = BloomFilter::rebuild_from(&words, Murmur3, 0.01);
+        assert!(rebuilt.capacity_bits() < bloom_filter.capacity_bits() / 100);
+        assert!(rebuilt.contains_all(&words));
+        assert_eq!(100, rebuilt.len());
+    }
+
+    #[test]
+    fn test_hashable() {
+        #[derive(Hash)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        for i in 0..500_u64 {
+            bloom_filter.insert_hashable(&i);
+        }
+        bloom_filter.insert_hashable(&Point { x: 1, y: 2 });
+
+        for i in 0..500_u64 {
+            assert!(bloom_filter.contains_hashable(&i));
+        }
+        assert!(bloom_filter.contains_hashable(&Point { x: 1, y: 2 }));
+        assert!(!bloom_filter.contains_hashable(&Point { x: 2, y: 1 }));
+        assert!(bloom_filter.contains(7_u64.to_ne_bytes()));
+        assert_eq!(501, bloom_filter.len());
+    }
+
+    #[test]
+    fn test_from_parts() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        bloom_filter.insert_all(&["apple", "banana", "cherry"]);
+
+        let rebuilt = BloomFilter::from_parts(
+            Murmur3,
+            bloom_filter.num_hashes(),
+            bloom_filter.bit_vec().clone(),
+            bloom_filter.len(),
+        )
+        .unwrap();
+        assert_eq!(bloom_filter, rebuilt);
+        assert!(rebuilt.contains("banana"));
+
+        assert_eq!(
+            Some(BloomError::ZeroHashFunctions),
+            BloomFilter::from_parts(Murmur3, 0, BitVec::from_elem(100, false), 0).err()
+        );
+        assert_eq!(
+            Some(BloomError::ZeroArraySize),
+            BloomFilter::from_parts(Murmur3, 7, BitVec::new(), 0).err()
+        );
+    }
+
+    #[test]
+    fn test_seed_offset() {
+        let words = ["apple", "banana", "cherry"];
+
+        let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+        let mut b = BloomFilter::optimal(Murmur3, 100, 0.01).with_seed_offset(42);
+        a.insert_all(&words);
+        b.insert_all(&words);
+
+        assert_eq!(0, a.seed_offset());
+        assert_eq!(42, b.seed_offset());
+        assert!(a.bit_vec != b.bit_vec);
+        for word in words.iter() {
+            assert!(b.contains(word));
+        }
+
+        let deserialized = BloomFilter::from_bytes(Murmur3, &b.to_bytes()).unwrap();
+        assert_eq!(b, deserialized);
+        for word in words.iter() {
+            assert!(deserialized.contains(word));
+        }
+
+        assert_eq!(Some(BloomError::IncompatibleDimensions), a.union(&b).err());
+    }
+
+    #[test]
+    fn test_optimal_salted() {
+        let words: Vec<String> = (0..100).map(|i| format!("word{}", i)).collect();
+
+        let mut a = BloomFilter::optimal_salted(Murmur3, 100, 0.01, 1);
+        let mut b = BloomFilter::optimal_salted(Murmur3, 100, 0.01, 2);
+        a.insert_all(&words);
+        b.insert_all(&words);
+
+        assert!(a.bit_vec != b.bit_vec);
+        assert!(a.contains_all(&words));
+        assert!(b.contains_all(&words));
+
+        let deserialized = BloomFilter::from_bytes(Murmur3, &b.to_bytes()).unwrap();
+        assert_eq!(2, deserialized.seed_offset());
+        assert!(deserialized.contains_all(&words));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_new_random() {
+        let words: Vec<String> = (0..100).map(|i| format!("word{}", i)).collect();
+
+        let mut a = BloomFilter::new_random(Murmur3, 100, 0.01);
+        let mut b = BloomFilter::new_random(Murmur3, 100, 0.01);
+        a.insert_all(&words);
+        b.insert_all(&words);
+
+        // The salts collide with a probability of 2^-32.
+        assert!(a.seed_offset() != b.seed_offset());
+        assert!(a.bit_vec != b.bit_vec);
+        assert!(a.contains_all(&words));
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        bloom_filter.insert_all(&["apple", "banana"]);
+
+        let snapshot = bloom_filter.clone();
+        bloom_filter.clear();
+        bloom_filter.insert(b"cherry");
+
+        assert!(snapshot.contains("apple"));
+        assert!(snapshot.contains("banana"));
+        assert!(!snapshot.contains("cherry"));
+        assert_eq!(2, snapshot.len());
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut bloom_filter = BloomFilter::new(Murmur3, 3, 100);
+        bloom_filter.insert(b"hello");
+
+        let debug = format!("{:?}", bloom_filter);
+        assert!(debug.contains("Murmur3"));
+        assert!(debug.contains("k: 3"));
+        assert!(debug.contains("bits: 100"));
+        assert!(debug.contains("insert_count: 1"));
+        assert!(debug.contains("fill_ratio: 0.03"));
+    }
+
+    #[test]
+    fn test_eq() {
+        let words = ["apple", "banana", "cherry"];
+
+        let mut a = BloomFilter::optimal(Murmur3, 100, 0.01);
+        let mut b = BloomFilter::optimal(Murmur3, 100, 0.01);
+        a.insert_all(&words);
+        b.insert_all(&words);
+        assert_eq!(a, b);
+
+        b.insert(b"durian");
+        assert!(a != b);
+
+        let mut c = BloomFilter::new(Murmur3, a.k + 1, a.capacity_bits());
+        c.insert_all(&words);
+        assert!(a != c);
+    }
+
+    #[test]
+    fn test_default() {
+        let bloom_filter = BloomFilter::default();
+        assert_eq!(7, bloom_filter.num_hashes());
+        assert_eq!(9586, bloom_filter.capacity_bits());
+        assert!(bloom_filter.is_empty());
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        assert_eq!(7, bloom_filter.num_hashes());
+        assert_eq!(95851, bloom_filter.capacity_bits());
+        assert_eq!(0, bloom_filter.len());
+        assert!(bloom_filter.is_empty());
+
+        bloom_filter.insert(b"hello");
+        bloom_filter.insert(b"hello");
+        assert_eq!(2, bloom_filter.len());
+        assert!(!bloom_filter.is_empty());
+    }
+
+    #[test]
+    fn test_set_bits() {
+        let mut bloom_filter = BloomFilter::new(Murmur3, 7, 1000);
+        assert_eq!(0, bloom_filter.set_bits().count());
+
+        bloom_filter.insert(b"hello");
+
+        let mut expected: Vec<usize> = bloom_filter.probes(b"hello").collect();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(expected, bloom_filter.set_bits().collect::<Vec<usize>>());
+
+        let naive: Vec<usize> = (0..1000).filter(|&i| bloom_filter.bit_vec[i]).collect();
+        assert_eq!(naive, bloom_filter.set_bits().collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_memory_bytes() {
+        let bloom_filter = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        assert_eq!(95851, bloom_filter.capacity_bits());
+
+        let struct_size = mem::size_of::<BloomFilter<Murmur3>>();
+        assert_eq!(11984 + struct_size, bloom_filter.memory_bytes());
+    }
+
+    #[test]
+    fn test_as_blocks() {
+        let mut bloom_filter = BloomFilter::new(Murmur3, 7, 1001);
+        bloom_filter.insert_all(&["apple", "banana", "cherry"]);
+
+        let blocks = bloom_filter.as_blocks();
+        assert_eq!(1001_usize.div_ceil(32), blocks.len());
+
+        let mut bytes: Vec<u8> = blocks.iter().flat_map(|block| block.to_le_bytes()).collect();
+        bytes.truncate(1001_usize.div_ceil(8));
+        assert_eq!(&bloom_filter.to_bytes()[HEADER_LEN..], &bytes[..]);
+    }
+
+    #[test]
+    fn test_count_ones() {
+        for &len in [1_u64, 31, 32, 33, 1000].iter() {
+            let mut bloom_filter = BloomFilter::new(Murmur3, 3, len);
+            assert_eq!(0, bloom_filter.count_ones());
+
+            for i in 0..20_u32 {
+                bloom_filter.insert(i.to_le_bytes());
+            }
+            let naive = bloom_filter.bit_vec.iter().filter(|&bit| bit).count() as u64;
+            assert_eq!(naive, bloom_filter.count_ones());
+        }
+    }
+
+    #[test]
+    fn test_with_random_fill() {
+        for fill_ratio in [0.0, 0.1, 0.5, 0.9, 1.0].iter() {
+            let bloom_filter = BloomFilter::with_random_fill(Murmur3, 7, 10000, *fill_ratio, 42);
+
+            assert!((bloom_filter.fill_ratio() - fill_ratio).abs() < 0.001);
+            assert_eq!(0, bloom_filter.len());
+        }
+
+        assert_eq!(
+            BloomFilter::with_random_fill(Murmur3, 7, 10000, 0.3, 42),
+            BloomFilter::with_random_fill(Murmur3, 7, 10000, 0.3, 42)
+        );
+        assert_ne!(
+            BloomFilter::with_random_fill(Murmur3, 7, 10000, 0.3, 42),
+            BloomFilter::with_random_fill(Murmur3, 7, 10000, 0.3, 43)
+        );
     }
 
     #[test]
     #[should_panic]
-    fn test_error_rate_too_high() {
-        BloomFilter::optimal(Murmur3, 10000, 1_f64);
+    fn test_with_random_fill_invalid_ratio() {
+        BloomFilter::with_random_fill(Murmur3, 7, 10000, 1.5, 42);
+    }
+
+    #[test]
+    fn test_fill_ratio() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        assert_eq!(0_f64, bloom_filter.fill_ratio());
+
+        let mut previous = 0_f64;
+        for i in 0..1000_u32 {
+            bloom_filter.insert(i.to_le_bytes());
+
+            let fill_ratio = bloom_filter.fill_ratio();
+            assert!(fill_ratio >= previous);
+            previous = fill_ratio;
+        }
+
+        assert!((previous - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_estimate_cardinality() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        assert_eq!(0_f64, bloom_filter.estimate_cardinality());
+
+        for n in [100_u32, 1000, 5000, 10000].iter() {
+            bloom_filter.clear();
+            for i in 0..*n {
+                bloom_filter.insert(i.to_le_bytes());
+                bloom_filter.insert(i.to_le_bytes());
+            }
+
+            let estimate = bloom_filter.estimate_cardinality();
+            let error = (estimate - *n as f64).abs() / *n as f64;
+            assert!(error < 0.03, "estimated {} for {}", estimate, n);
+        }
+    }
+
+    #[test]
+    fn test_try_estimate_cardinality() {
+        let mut bloom_filter = BloomFilter::new(Murmur3, 7, 1000);
+        assert_eq!(Some(0_f64), bloom_filter.try_estimate_cardinality());
+
+        // An element whose probes collide sets fewer than 7 bits, which the
+        // uncorrected estimator would report as less than one element.
+        let mut bit_vec = BitVec::from_elem(1000, false);
+        bit_vec.set(42, true);
+        let single = BloomFilter::from_parts(Murmur3, 7, bit_vec, 1).unwrap();
+        assert!(single.cardinality_from_set_bits(1) < 1_f64);
+        assert_eq!(Some(1_f64), single.try_estimate_cardinality());
+
+        // At half fill, m / k * ln(2) elements were inserted on average.
+        let mut i = 0_u32;
+        while bloom_filter.fill_ratio() < 0.5 {
+            bloom_filter.insert(i.to_le_bytes());
+            i += 1;
+        }
+        let estimate = bloom_filter.try_estimate_cardinality().unwrap();
+        assert!((estimate - 1000_f64 / 7_f64 * LN_2).abs() < 1_f64);
+        assert!((estimate - i as f64).abs() < 15_f64, "estimated {} for {}", estimate, i);
+
+        while bloom_filter.fill_ratio() < 1_f64 {
+            bloom_filter.insert(i.to_le_bytes());
+            i += 1;
+        }
+        assert_eq!(None, bloom_filter.try_estimate_cardinality());
+
+        // The saturated estimate is finite, and a lower bound of the estimate
+        // for any fill below saturation.
+        let estimate = bloom_filter.estimate_cardinality();
+        assert!(estimate.is_finite());
+        assert_eq!(bloom_filter.cardinality_from_set_bits(999), estimate);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 500, 0.01);
+        bloom_filter.insert_all(&words[..500]);
+
+        let bytes = bloom_filter.to_bytes();
+        assert_eq!(HEADER_LEN + bloom_filter.bit_vec.len().div_ceil(8), bytes.len());
+
+        let deserialized = BloomFilter::from_bytes(Murmur3, &bytes).unwrap();
+        assert_eq!(bloom_filter, deserialized);
+        for word in words.iter() {
+            assert_eq!(bloom_filter.contains(word), deserialized.contains(word));
+        }
+    }
+
+    #[test]
+    fn test_bytes_layout() {
+        let mut bloom_filter = BloomFilter::new(Murmur3, 2, 10).with_seed_offset(3);
+        bloom_filter.bit_vec.set(0, true);
+        bloom_filter.bit_vec.set(9, true);
+        bloom_filter.insert_count = 1;
+
+        assert_eq!(
+            vec![
+                b'B', b'L', b'M', b'F', 2,
+                2, 0, 0, 0,
+                3, 0, 0, 0,
+                1, 0, 0, 0, 0, 0, 0, 0,
+                10, 0, 0, 0, 0, 0, 0, 0,
+                0b0000_0001, 0b0000_0010,
+            ],
+            bloom_filter.to_bytes()
+        );
+    }
+
+    #[test]
+    fn test_bytes_independent_of_endianness() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 500, 0.01);
+        bloom_filter.insert_all(&words[..500]);
+        let bytes = bloom_filter.to_bytes();
+
+        // A host of the other endianness holds the same block values with
+        // their bytes swapped in memory.
+        let foreign_memory: Vec<[u8; 4]> = bloom_filter
+            .as_blocks()
+            .iter()
+            .map(|block| block.swap_bytes().to_ne_bytes())
+            .collect();
+
+        // Dumping that memory as is would not be portable.
+        let mut dumped: Vec<u8> = bytes[..HEADER_LEN].to_vec();
+        dumped.extend(foreign_memory.iter().flatten());
+        dumped.truncate(bytes.len());
+        assert_ne!(bytes, dumped);
+
+        // Converting each block to little-endian, as `to_bytes` does on every
+        // host, writes the same bytes there.
+        let mut written: Vec<u8> = bytes[..HEADER_LEN].to_vec();
+        for image in foreign_memory.iter() {
+            let block = u32::from_ne_bytes(*image).swap_bytes();
+            written.extend_from_slice(&block.to_le_bytes());
+        }
+        written.truncate(bytes.len());
+        assert_eq!(bytes, written);
+
+        let deserialized = BloomFilter::from_bytes(Murmur3, &written).unwrap();
+        for word in words.iter() {
+            assert_eq!(bloom_filter.contains(word), deserialized.contains(word));
+        }
+        assert!(words[..500].iter().all(|word| deserialized.contains(word)));
+    }
+
+    #[test]
+    fn test_from_bytes_version_1() {
+        let bytes = [
+            b'B', b'L', b'M', b'F', 1,
+            2, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0,
+            10, 0, 0, 0, 0, 0, 0, 0,
+            0b0000_0001, 0b0000_0010,
+        ];
+
+        let bloom_filter = BloomFilter::from_bytes(Murmur3, &bytes).unwrap();
+        assert_eq!(2, bloom_filter.k);
+        assert_eq!(0, bloom_filter.seed_offset);
+        assert_eq!(1, bloom_filter.insert_count);
+        assert!(bloom_filter.bit_vec.eq_vec(&[
+            true, false, false, false, false, false, false, false, false, true
+        ]));
+    }
+
+    #[test]
+    fn test_from_bytes_corrupt() {
+        let bytes = BloomFilter::optimal(Murmur3, 100, 0.01).to_bytes();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 0;
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+
+        let truncated = bytes[..bytes.len() - 1].to_vec();
+        let header_only = bytes[..10].to_vec();
+
+        for data in [bad_magic, bad_version, trailing, truncated, header_only].iter() {
+            assert_eq!(Some(BloomError::CorruptData), BloomFilter::from_bytes(Murmur3, data).err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_write_to_read_from() {
+        // Large enough to be streamed in several chunks, and not a whole
+        // number of blocks.
+        let mut bloom_filter = BloomFilter::new(Murmur3, 7, 1_000_001).with_seed_offset(5);
+        for i in 0..50000_u32 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+
+        let mut cursor = Cursor::new(Vec::new());
+        bloom_filter.write_to(&mut cursor).unwrap();
+        assert_eq!(bloom_filter.to_bytes(), *cursor.get_ref());
+
+        cursor.get_mut().extend_from_slice(b"next");
+        cursor.set_position(0);
+        let deserialized = BloomFilter::read_from(Murmur3, &mut cursor).unwrap();
+        assert_eq!(bloom_filter, deserialized);
+        assert_eq!(bloom_filter.to_bytes().len() as u64, cursor.position());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_read_from_invalid() {
+        let bytes = BloomFilter::optimal(Murmur3, 100, 0.01).to_bytes();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 0;
+        for data in [bad_magic, bad_version].iter() {
+            let error = BloomFilter::read_from(Murmur3, &mut &data[..]).err().unwrap();
+            assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        }
+
+        let error = BloomFilter::read_from(Murmur3, &mut &bytes[..bytes.len() - 1]).err().unwrap();
+        assert_eq!(io::ErrorKind::UnexpectedEof, error.kind());
+
+        // A header claiming far more bits than follow must not allocate them.
+        let mut huge = bytes[..HEADER_LEN].to_vec();
+        huge[21..29].copy_from_slice(&(1_u64 << 62).to_le_bytes());
+        huge.extend_from_slice(&[0xff; 16]);
+        let error = BloomFilter::read_from(Murmur3, &mut &huge[..]).err().unwrap();
+        assert_eq!(io::ErrorKind::UnexpectedEof, error.kind());
+    }
+
+    #[test]
+    fn test_probes() {
+        let bloom_filter = BloomFilter::new(Murmur3, 7, 1000);
+        let h1 = Murmur3.hash64(0, b"hello");
+        let h2 = h1.rotate_left(32);
+        let hash = Murmur3.hash128(0, b"hello");
+        assert_eq!((h1, h2), (hash as u64, (hash >> 64) as u64));
+
+        let expected: Vec<usize> = (0..7_u64)
+            .map(|i| fastrange(h1.wrapping_add(i.wrapping_mul(h2)), 1000))
+            .collect();
+        assert_eq!(expected, bloom_filter.probes(b"hello").collect::<Vec<usize>>());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_probes_beyond_u32() {
+        let len = 1_usize << 40;
+        let max = (0..1000_u32)
+            .flat_map(|i| Probes::new(&Murmur3, 0, 7, len, &i.to_le_bytes()))
+            .max()
+            .unwrap();
+
+        assert!(max > u32::MAX as usize);
+        assert!(max < len);
     }
 
     #[test]
+    fn test_fastrange() {
+        assert_eq!(0, fastrange(0, 1000));
+        assert_eq!(999, fastrange(u64::MAX, 1000));
+        assert_eq!(500, fastrange(1 << 63, 1000));
+    }
+
+    #[test]
+    fn test_probe_distribution() {
+        const BUCKETS: usize = 10;
+        const SAMPLES: u32 = 100000;
+
+        let bloom_filter = BloomFilter::new(Murmur3, 1, 3000);
+        let mut counts = [0_f64; BUCKETS];
+        for i in 0..SAMPLES {
+            for index in bloom_filter.probes(&i.to_le_bytes()) {
+                counts[index * BUCKETS / 3000] += 1_f64;
+            }
+        }
+
+        // The 99.9th percentile of the chi-square distribution with 9 degrees
+        // of freedom is roughly 27.9
+        let expected = SAMPLES as f64 / BUCKETS as f64;
+        let chi_square: f64 = counts.iter().map(|c| (c - expected).powi(2) / expected).sum();
+        assert!(chi_square < 27.9, "chi-square of {} is too high", chi_square);
+    }
+
+    #[test]
+    fn test_no_false_negatives_long_keys() {
+        let keys: Vec<Vec<u8>> = (0..1000_u32)
+            .map(|i| i.to_le_bytes().iter().cycle().cloned().take(64).collect())
+            .collect();
+
+        let mut bloom_filter = BloomFilter::new(Murmur3, 7, 9586);
+        bloom_filter.insert_all(&keys);
+
+        for key in keys.iter() {
+            assert!(bloom_filter.contains(key));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
     fn test_no_false_negatives() {
         let words: Vec<String> = BufReader::new(File::open("./resources/1000.txt").unwrap())
             .lines()
@@ -191,13 +3804,32 @@ mod tests {
         bloom_filter.insert_all(&words);
 
         for word in words.iter() {
-            assert!(bloom_filter.contains(&word));
-        }
-//
-//        let bloom_filter = BloomFilter::from_iter(words.iter(), Murmur3, 0.01);
-//
-//        for word in words.iter() {
-//            assert!(bloom_filter.contains(&word));
-//        }
+            assert!(bloom_filter.contains(word));
+        }
+
+        let bloom_filter = BloomFilter::from_iter(words.iter(), Murmur3, 0.01);
+
+        for word in words.iter() {
+            assert!(bloom_filter.contains(word));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_insert_iter_lines() {
+        let lines = BufReader::new(File::open("./resources/1000.txt").unwrap()).lines();
+
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        bloom_filter.insert_iter(lines.map(|s| s.unwrap()));
+
+        let words: Vec<String> = BufReader::new(File::open("./resources/1000.txt").unwrap())
+            .lines()
+            .map(|s| s.unwrap())
+            .collect();
+        let mut expected = BloomFilter::optimal(Murmur3, 1000, 0.01);
+        expected.insert_all(&words);
+
+        assert_eq!(expected, bloom_filter);
+        assert_eq!(words.len() as u64, bloom_filter.len());
     }
 }
\ No newline at end of file
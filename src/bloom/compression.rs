@@ -0,0 +1,131 @@
+use flate2::bufread::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use std::io::Read;
+
+#[cfg(feature = "stats")]
+use super::FilterStats;
+use super::{read_bits, BloomFilter, Header, DEFAULT_ERROR_RATE};
+use error::BloomError;
+use hash::BloomHasher;
+
+/// The magic bytes at the start of the compressed binary format.
+const COMPRESSED_MAGIC: &[u8; 4] = b"BLMZ";
+
+/// The maximum ratio between the decompressed and compressed lengths of a
+/// zlib stream, the limit of the deflate format.
+const MAX_EXPANSION: usize = 1032;
+
+impl<T: BloomHasher> BloomFilter<T> {
+    /// Serialize the `BloomFilter` into a compressed binary format.
+    ///
+    /// The format is that of `to_bytes`, with different magic bytes and the
+    /// packed bits compressed using zlib. The header is left uncompressed, and
+    /// the bit array grows as the bits are decompressed, so a header claiming
+    /// more bits than the data holds is rejected without allocating them.
+    /// Filters that are far from full have long runs of zero bits and compress
+    /// well, while a full filter is close to random and does not compress at all.
+    pub fn to_bytes_compressed(&self) -> Vec<u8> {
+        let mut header = self.header();
+        header[0..4].copy_from_slice(COMPRESSED_MAGIC);
+
+        let mut encoder = ZlibEncoder::new(header.to_vec(), Compression::default());
+        self.write_bits(&mut encoder)
+            .and_then(|()| encoder.finish())
+            .expect("writing to a Vec cannot fail")
+    }
+
+    /// Deserialize a `BloomFilter` written by `to_bytes_compressed`.
+    ///
+    /// Returns `BloomError::CorruptData` if the magic bytes or version do not
+    /// match, or if the compressed bits are invalid, truncated or have
+    /// trailing bytes.
+    pub fn from_bytes_compressed(hasher: T, data: &[u8]) -> Result<Self, BloomError> {
+        if data.len() < 5 || &data[0..4] != COMPRESSED_MAGIC {
+            return Err(BloomError::CorruptData);
+        }
+
+        let mut rest = &data[5..];
+        let header = Header::parse_fields(data[4], &mut rest)?;
+        if header.bit_len.div_ceil(8) > rest.len().saturating_mul(MAX_EXPANSION) {
+            return Err(BloomError::CorruptData);
+        }
+
+        let mut decoder = ZlibDecoder::new(rest);
+        let bit_vec = read_bits(&mut decoder, header.bit_len).map_err(|_| BloomError::CorruptData)?;
+        match decoder.read(&mut [0]) {
+            Ok(0) if decoder.into_inner().is_empty() => {}
+            _ => return Err(BloomError::CorruptData),
+        }
+
+        Ok(Self {
+            hasher,
+            k: header.k,
+            seed_offset: header.seed_offset,
+            bit_vec,
+            insert_count: header.insert_count,
+            target_error_rate: DEFAULT_ERROR_RATE,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bloom::BloomFilter;
+    use error::BloomError;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_round_trip() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 10000, 0.01).with_seed_offset(3);
+        for i in 0..1000_u32 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+
+        let bytes = bloom_filter.to_bytes_compressed();
+        let deserialized = BloomFilter::from_bytes_compressed(Murmur3, &bytes).unwrap();
+        assert_eq!(bloom_filter, deserialized);
+    }
+
+    #[test]
+    fn test_compressed_size() {
+        // With 7 hash functions, 1000 elements set roughly 7% of the bits.
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        for i in 0..1000_u32 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+        assert!(bloom_filter.fill_ratio() < 0.1);
+
+        let raw = bloom_filter.to_bytes().len();
+        let compressed = bloom_filter.to_bytes_compressed().len();
+        assert!(compressed < raw * 3 / 4, "{} compressed vs {} raw", compressed, raw);
+    }
+
+    #[test]
+    fn test_corrupt() {
+        let bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        let bytes = bloom_filter.to_bytes_compressed();
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let truncated = bytes[..bytes.len() - 1].to_vec();
+        let uncompressed = bloom_filter.to_bytes();
+        // Headers claiming more bits than the compressed data can hold, and
+        // more bits than it does hold.
+        let mut huge = bytes.clone();
+        huge[21..29].copy_from_slice(&(1_u64 << 62).to_le_bytes());
+        let mut long = bytes.clone();
+        let bit_len = (bytes.len() as u64 - 29) * 8 * 1000;
+        long[21..29].copy_from_slice(&bit_len.to_le_bytes());
+
+        for data in [trailing, truncated, uncompressed, huge, long].iter() {
+            assert_eq!(
+                Some(BloomError::CorruptData),
+                BloomFilter::from_bytes_compressed(Murmur3, data).err()
+            );
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use memmap2::Mmap;
+
+use std::fs::File;
+use std::hint;
+use std::io;
+use std::path::Path;
+
+use super::{BloomFilter, Header, Probes, PAGE_LEN};
+use hash::BloomHasher;
+
+/// MmapBloomFilter
+///
+/// A read-only bloom filter backed by a memory-mapped file written by
+/// `BloomFilter::to_bytes`.
+///
+/// The bits are read directly from the mapped file, so filters larger than
+/// the available memory can be queried. Every field of the format is stored
+/// little-endian and the bits are addressed byte by byte, so the file has no
+/// alignment requirements and can be mapped on any platform.
+///
+/// The file must not be modified while it is mapped.
+pub struct MmapBloomFilter<T> {
+    hasher: T,
+    k: u32,
+    seed_offset: u32,
+    bit_len: usize,
+    insert_count: u64,
+    header_len: usize,
+    mmap: Mmap,
+}
+
+impl<T: BloomHasher> BloomFilter<T> {
+    /// Open a file written by `to_bytes` as a read-only, memory-mapped filter.
+    ///
+    /// Returns an error with `io::ErrorKind::InvalidData` if the file is not a
+    /// valid filter.
+    pub fn open_mmap<P: AsRef<Path>>(hasher: T, path: P) -> io::Result<MmapBloomFilter<T>> {
+        let file = File::open(path)?;
+
+        // Safety: the mapping is only ever read, and the file must not be
+        // modified while mapped, as documented on `MmapBloomFilter`.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let header =
+            Header::parse(&mmap).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(MmapBloomFilter {
+            hasher,
+            k: header.k,
+            seed_offset: header.seed_offset,
+            bit_len: header.bit_len,
+            insert_count: header.insert_count,
+            header_len: header.len,
+            mmap,
+        })
+    }
+}
+
+impl<T: BloomHasher> MmapBloomFilter<T> {
+    /// Check whether a slice of bytes exists in the `MmapBloomFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        let bits = &self.mmap[self.header_len..];
+        Probes::new(&self.hasher, self.seed_offset, self.k, self.bit_len, bytes.as_ref())
+            .all(|index| bits[index / 8] & (1 << (index % 8)) != 0)
+    }
+
+    /// Read every page of the mapped file, so that the pages are faulted in
+    /// upfront instead of during the first queries.
+    ///
+    /// This does not change the results of `contains`, it is purely a latency
+    /// optimization.
+    pub fn warm(&self) {
+        for offset in (0..self.mmap.len()).step_by(PAGE_LEN) {
+            hint::black_box(self.mmap[offset]);
+        }
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the size of the underlying bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.bit_len as u64
+    }
+
+    /// Returns the number of times `insert` was called on the serialized filter.
+    pub fn len(&self) -> u64 {
+        self.insert_count
+    }
+
+    /// Returns `true` if nothing was inserted into the serialized filter.
+    pub fn is_empty(&self) -> bool {
+        self.insert_count == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_open_mmap() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 500, 0.01).with_seed_offset(7);
+        bloom_filter.insert_all(&words[..500]);
+
+        let path = env::temp_dir().join(format!("bloom-filter-mmap-{}", std::process::id()));
+        fs::write(&path, bloom_filter.to_bytes()).unwrap();
+
+        let mapped = BloomFilter::open_mmap(Murmur3, &path).unwrap();
+        mapped.warm();
+        assert_eq!(bloom_filter.num_hashes(), mapped.num_hashes());
+        assert_eq!(bloom_filter.capacity_bits(), mapped.capacity_bits());
+        assert_eq!(500, mapped.len());
+        for word in words.iter() {
+            assert_eq!(bloom_filter.contains(word), mapped.contains(word));
+        }
+
+        fs::write(&path, b"not a filter").unwrap();
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            BloomFilter::open_mmap(Murmur3, &path).err().unwrap().kind()
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+use rayon::prelude::*;
+
+use super::BloomFilter;
+use hash::BloomHasher;
+
+impl<T: BloomHasher + Sync> BloomFilter<T> {
+    /// Insert a slice of slices of bytes into the `BloomFilter`, hashing the
+    /// items in parallel.
+    ///
+    /// The bit indices of every item are computed in parallel, then set
+    /// sequentially. This trades memory for the indices, `k` per item, for
+    /// not needing atomic access to the bits. The resulting filter is
+    /// identical to one built with `insert_all`.
+    pub fn par_insert_all<B: AsRef<[u8]> + Sync>(&mut self, items: &[B]) {
+        let indices: Vec<usize> = items
+            .par_iter()
+            .flat_map_iter(|item| self.probes(item.as_ref()))
+            .collect();
+
+        for index in indices {
+            self.set_bit(index);
+        }
+        self.insert_count += items.len() as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_par_insert_all() {
+        let words: Vec<String> = (0..10000).map(|i| format!("word{}", i)).collect();
+
+        let mut sequential = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        sequential.insert_all(&words);
+
+        let mut parallel = BloomFilter::optimal(Murmur3, 10000, 0.01);
+        parallel.par_insert_all(&words);
+
+        assert_eq!(sequential, parallel);
+    }
+}
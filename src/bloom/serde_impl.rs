@@ -0,0 +1,118 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "stats")]
+use super::FilterStats;
+use super::{checked_len, pack_bits, unpack_bits, BloomFilter, DEFAULT_ERROR_RATE};
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+
+/// The serialized representation of a `BloomFilter`.
+///
+/// The hasher is not serialized, it is recreated using `Default` when
+/// deserializing.
+#[derive(Serialize, Deserialize)]
+struct SerializedBloomFilter {
+    k: u32,
+    seed_offset: u32,
+    insert_count: u64,
+    bit_len: u64,
+    bits: Vec<u8>,
+}
+
+impl<T: BloomHasher> Serialize for BloomFilter<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedBloomFilter {
+            k: self.k,
+            seed_offset: self.seed_offset,
+            insert_count: self.insert_count,
+            bit_len: self.bit_vec.len() as u64,
+            bits: pack_bits(&self.bit_vec),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: BloomHasher + Default> Deserialize<'de> for BloomFilter<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let serialized = SerializedBloomFilter::deserialize(deserializer)?;
+
+        if serialized.k == 0 {
+            return Err(D::Error::custom("number of hash functions must be non-zero"));
+        }
+        if serialized.bit_len == 0 {
+            return Err(D::Error::custom("bit length must be non-zero"));
+        }
+        if serialized.bits.len() as u64 != serialized.bit_len.div_ceil(8) {
+            return Err(D::Error::custom("bit length does not match the packed bits"));
+        }
+        let bit_len = checked_len(serialized.bit_len).map_err(D::Error::custom)?;
+
+        Ok(BloomFilter {
+            hasher: T::default(),
+            k: serialized.k,
+            seed_offset: serialized.seed_offset,
+            bit_vec: unpack_bits(&serialized.bits, bit_len),
+            insert_count: serialized.insert_count,
+            target_error_rate: DEFAULT_ERROR_RATE,
+            #[cfg(feature = "stats")]
+            stats: FilterStats::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode;
+
+    use super::*;
+    use bloom::FilterParameters;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_bincode_round_trip() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 500, 0.01).with_seed_offset(7);
+        bloom_filter.insert_all(&words[..500]);
+
+        let bytes = bincode::serialize(&bloom_filter).unwrap();
+        let deserialized: BloomFilter<Murmur3> = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(bloom_filter, deserialized);
+        for word in words.iter() {
+            assert_eq!(bloom_filter.contains(word), deserialized.contains(word));
+        }
+    }
+
+    #[test]
+    fn test_parameters_round_trip() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 500, 0.01).with_seed_offset(7);
+        bloom_filter.insert(b"hello");
+        let parameters = bloom_filter.parameters();
+
+        let bytes = bincode::serialize(&parameters).unwrap();
+        assert!(bytes.len() < 64);
+        let deserialized: FilterParameters = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(parameters, deserialized);
+        let compatible = BloomFilter::optimal(Murmur3, 500, 0.01).with_seed_offset(7);
+        assert!(deserialized.is_compatible(&compatible.parameters()));
+        let unsalted = BloomFilter::optimal(Murmur3, 500, 0.01);
+        assert!(!deserialized.is_compatible(&unsalted.parameters()));
+    }
+
+    #[test]
+    fn test_deserialize_mismatched_length() {
+        let serialized = SerializedBloomFilter {
+            k: 3,
+            seed_offset: 0,
+            insert_count: 0,
+            bit_len: 100,
+            bits: vec![0; 3],
+        };
+        let bytes = bincode::serialize(&serialized).unwrap();
+
+        assert!(bincode::deserialize::<BloomFilter<Murmur3>>(&bytes).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+use bloom::{check_array_size, optimal_hash_functions, optimal_parameters, BloomFilter};
+use error::BloomError;
+use hash::BloomHasher;
+use math;
+
+/// BloomFilterBuilder
+///
+/// A builder for configuring a `BloomFilter` one parameter at a time.
+///
+/// A hasher must always be given, along with one of the following:
+///
+/// - The expected number of elements and the error rate, computing the
+///   optimal array size and number of hash functions.
+/// - The expected number of elements and the array size, computing the
+///   optimal number of hash functions.
+/// - The number of hash functions and the array size.
+///
+/// Any other combination is rejected by `build`. A growth factor may be given
+/// along with the expected number of elements and the error rate, to leave
+/// headroom for more elements. A maximum array size may be given along with
+/// any of them, to reject parameters that would allocate too much memory.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bloom_filter_rs as bloom_filter;
+///
+/// use bloom_filter::{BloomFilterBuilder, Murmur3};
+///
+/// let bloom_filter = BloomFilterBuilder::new()
+///     .hasher(Murmur3)
+///     .max_elements(1000)
+///     .error_rate(0.01)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(7, bloom_filter.num_hashes());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BloomFilterBuilder<T> {
+    hasher: Option<T>,
+    max_elements: Option<u64>,
+    error_rate: Option<f64>,
+    k: Option<u32>,
+    array_size: Option<u64>,
+    growth_factor: Option<f64>,
+    max_bits: Option<u64>,
+}
+
+impl<T: BloomHasher> BloomFilterBuilder<T> {
+    /// Create a new `BloomFilterBuilder` with no parameters set.
+    pub fn new() -> Self {
+        Self {
+            hasher: None,
+            max_elements: None,
+            error_rate: None,
+            k: None,
+            array_size: None,
+            growth_factor: None,
+            max_bits: None,
+        }
+    }
+
+    /// Set the hasher.
+    pub fn hasher(mut self, hasher: T) -> Self {
+        self.hasher = Some(hasher);
+        self
+    }
+
+    /// Set the expected number of elements.
+    pub fn max_elements(mut self, max_elements: u64) -> Self {
+        self.max_elements = Some(max_elements);
+        self
+    }
+
+    /// Set the target error rate.
+    pub fn error_rate(mut self, error_rate: f64) -> Self {
+        self.error_rate = Some(error_rate);
+        self
+    }
+
+    /// Set the number of hash functions.
+    pub fn num_hashes(mut self, k: u32) -> Self {
+        self.k = Some(k);
+        self
+    }
+
+    /// Set the size of the bit array.
+    pub fn array_size(mut self, array_size: u64) -> Self {
+        self.array_size = Some(array_size);
+        self
+    }
+
+    /// Set a factor by which the optimal array size is multiplied, for a set
+    /// that is expected to grow past the expected number of elements.
+    ///
+    /// The number of hash functions is recomputed for the larger array. This
+    /// trades memory for headroom: a factor of 2 doubles the memory and roughly
+    /// squares the false positive rate at the expected number of elements, and
+    /// the filter holds about 1.8 times as many elements before reaching the
+    /// error rate. It can only be combined with the expected number of
+    /// elements and the error rate.
+    pub fn growth_factor(mut self, growth_factor: f64) -> Self {
+        self.growth_factor = Some(growth_factor);
+        self
+    }
+
+    /// Set the largest array size, in bits, that `build` may allocate.
+    ///
+    /// Very low error rates or very large numbers of elements can compute an
+    /// array size of many gigabytes. With a maximum, `build` returns
+    /// `BloomError::CapacityTooLarge` for such parameters instead of
+    /// attempting the allocation. Without one, only sizes that do not fit in
+    /// a `usize` are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomError, BloomFilterBuilder, Murmur3};
+    ///
+    /// let result = BloomFilterBuilder::new()
+    ///     .hasher(Murmur3)
+    ///     .max_elements(1_000_000_000)
+    ///     .error_rate(1e-12)
+    ///     .max_bits(1 << 33)
+    ///     .build();
+    ///
+    /// assert_eq!(Some(BloomError::CapacityTooLarge), result.err());
+    /// ```
+    pub fn max_bits(mut self, max_bits: u64) -> Self {
+        self.max_bits = Some(max_bits);
+        self
+    }
+
+    /// Build the `BloomFilter`, returning an error if the parameters are
+    /// invalid, conflicting, or not sufficient to size the filter.
+    pub fn build(self) -> Result<BloomFilter<T>, BloomError> {
+        let hasher = self.hasher.ok_or(BloomError::MissingParameters)?;
+        let max_bits = self.max_bits.unwrap_or(u64::MAX);
+        let check_max_bits = |m: u64| {
+            if m > max_bits {
+                return Err(BloomError::CapacityTooLarge);
+            }
+
+            Ok(m)
+        };
+
+        if let Some(growth_factor) = self.growth_factor {
+            return match (self.max_elements, self.error_rate, self.k, self.array_size) {
+                (Some(max_elements), Some(error_rate), None, None) => {
+                    if !(growth_factor.is_finite() && growth_factor >= 1_f64) {
+                        return Err(BloomError::InvalidGrowthFactor(growth_factor));
+                    }
+
+                    let (_, m) = optimal_parameters(max_elements, error_rate)?;
+                    let m = math::ceil(m as f64 * growth_factor) as u64;
+                    let m = check_max_bits(check_array_size(m, usize::MAX as u64)?)?;
+                    let k = optimal_hash_functions(m, max_elements);
+                    Ok(BloomFilter::new(hasher, k, m).with_target_error_rate(error_rate))
+                }
+                (_, _, Some(_), _) | (_, _, _, Some(_)) => Err(BloomError::ConflictingParameters),
+                _ => Err(BloomError::MissingParameters),
+            };
+        }
+
+        match (self.max_elements, self.error_rate, self.k, self.array_size) {
+            (Some(max_elements), Some(error_rate), None, None) => {
+                let (k, m) = optimal_parameters(max_elements, error_rate)?;
+                check_max_bits(m)?;
+                Ok(BloomFilter::new(hasher, k, m).with_target_error_rate(error_rate))
+            }
+            (Some(max_elements), None, None, Some(array_size)) => {
+                if max_elements == 0 {
+                    return Err(BloomError::ZeroElements);
+                }
+                if array_size == 0 {
+                    return Err(BloomError::ZeroArraySize);
+                }
+
+                let k = optimal_hash_functions(array_size, max_elements);
+                BloomFilter::try_new(hasher, k, check_max_bits(array_size)?)
+            }
+            (None, None, Some(k), Some(array_size)) => {
+                if k == 0 {
+                    return Err(BloomError::ZeroHashFunctions);
+                }
+                if array_size == 0 {
+                    return Err(BloomError::ZeroArraySize);
+                }
+
+                BloomFilter::try_new(hasher, k, check_max_bits(array_size)?)
+            }
+            (_, Some(_), Some(_), _) | (_, Some(_), _, Some(_)) | (Some(_), _, Some(_), Some(_)) => {
+                Err(BloomError::ConflictingParameters)
+            }
+            _ => Err(BloomError::MissingParameters),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    fn builder() -> BloomFilterBuilder<Murmur3> {
+        BloomFilterBuilder::new().hasher(Murmur3)
+    }
+
+    #[test]
+    fn test_max_elements_and_error_rate() {
+        let bloom_filter = builder().max_elements(10000).error_rate(0.01).build().unwrap();
+        assert_eq!(BloomFilter::optimal(Murmur3, 10000, 0.01), bloom_filter);
+    }
+
+    #[test]
+    fn test_max_elements_and_array_size() {
+        let bloom_filter = builder().max_elements(10000).array_size(95851).build().unwrap();
+        assert_eq!(7, bloom_filter.num_hashes());
+        assert_eq!(95851, bloom_filter.capacity_bits());
+    }
+
+    #[test]
+    fn test_num_hashes_and_array_size() {
+        let bloom_filter = builder().num_hashes(3).array_size(100).build().unwrap();
+        assert_eq!(BloomFilter::new(Murmur3, 3, 100), bloom_filter);
+    }
+
+    #[test]
+    fn test_growth_factor() {
+        let bloom_filter = builder().max_elements(10000).error_rate(0.01).build().unwrap();
+        let grown = builder()
+            .max_elements(10000)
+            .error_rate(0.01)
+            .growth_factor(2_f64)
+            .build()
+            .unwrap();
+
+        assert_eq!(bloom_filter.capacity_bits() * 2, grown.capacity_bits());
+        assert_eq!(13, grown.num_hashes());
+        // Doubling the bits per element roughly squares the error rate.
+        assert!(grown.projected_false_positive_rate(10000) < 0.0002);
+        assert!(grown.capacity_for_error(0.01) > 17000);
+        assert_eq!(0.01, grown.target_error_rate());
+
+        let strict = builder().max_elements(10000).error_rate(0.001).growth_factor(2_f64);
+        assert_eq!(0.001, strict.build().unwrap().target_error_rate());
+
+        let unchanged = builder().max_elements(10000).error_rate(0.01).growth_factor(1_f64);
+        assert_eq!(bloom_filter, unchanged.build().unwrap());
+    }
+
+    #[test]
+    fn test_invalid_growth_factor() {
+        for factor in [0.5, -1_f64, f64::NAN, f64::INFINITY].iter() {
+            let builder = builder().max_elements(100).error_rate(0.01).growth_factor(*factor);
+            assert!(matches!(builder.build().err(), Some(BloomError::InvalidGrowthFactor(_))));
+        }
+
+        assert_eq!(
+            Some(BloomError::ConflictingParameters),
+            builder().max_elements(100).array_size(100).growth_factor(2_f64).build().err()
+        );
+        assert_eq!(
+            Some(BloomError::MissingParameters),
+            builder().max_elements(100).growth_factor(2_f64).build().err()
+        );
+    }
+
+    #[test]
+    fn test_max_bits() {
+        // About 7 GB of bits, computed without allocating.
+        let (_, m) = optimal_parameters(1_000_000_000, 1e-12).unwrap();
+        assert!(m > 1 << 35);
+
+        let huge = builder().max_elements(1_000_000_000).error_rate(1e-12).max_bits(1 << 33);
+        assert_eq!(Some(BloomError::CapacityTooLarge), huge.build().err());
+
+        let sized = [
+            builder().max_elements(10000).error_rate(0.01),
+            builder().max_elements(10000).error_rate(0.01).growth_factor(2_f64),
+            builder().max_elements(10000).array_size(95851),
+            builder().num_hashes(7).array_size(95851),
+        ];
+        for builder in sized.iter() {
+            let bits = builder.clone().build().unwrap().capacity_bits();
+            assert!(builder.clone().max_bits(bits).build().is_ok());
+            assert_eq!(
+                Some(BloomError::CapacityTooLarge),
+                builder.clone().max_bits(bits - 1).build().err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert_eq!(
+            Some(BloomError::InvalidErrorRate(2_f64)),
+            builder().max_elements(100).error_rate(2_f64).build().err()
+        );
+        assert_eq!(
+            Some(BloomError::ZeroElements),
+            builder().max_elements(0).array_size(100).build().err()
+        );
+        assert_eq!(
+            Some(BloomError::ZeroArraySize),
+            builder().max_elements(100).array_size(0).build().err()
+        );
+        assert_eq!(
+            Some(BloomError::ZeroHashFunctions),
+            builder().num_hashes(0).array_size(100).build().err()
+        );
+        assert_eq!(
+            Some(BloomError::ZeroArraySize),
+            builder().num_hashes(3).array_size(0).build().err()
+        );
+    }
+
+    #[test]
+    fn test_conflicting_parameters() {
+        let conflicting = [
+            builder().error_rate(0.01).array_size(100),
+            builder().error_rate(0.01).num_hashes(3),
+            builder().max_elements(100).error_rate(0.01).array_size(100),
+            builder().max_elements(100).num_hashes(3).array_size(100),
+            builder().max_elements(100).error_rate(0.01).num_hashes(3).array_size(100),
+        ];
+
+        for builder in conflicting.iter() {
+            assert_eq!(Some(BloomError::ConflictingParameters), builder.clone().build().err());
+        }
+    }
+
+    #[test]
+    fn test_missing_parameters() {
+        let missing = [
+            BloomFilterBuilder::new().max_elements(100).error_rate(0.01),
+            builder(),
+            builder().max_elements(100),
+            builder().error_rate(0.01),
+            builder().num_hashes(3),
+            builder().array_size(100),
+            builder().max_elements(100).num_hashes(3),
+        ];
+
+        for builder in missing.iter() {
+            assert_eq!(Some(BloomError::MissingParameters), builder.clone().build().err());
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use bloom::BloomFilter;
+use error::BloomError;
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+
+/// ChainedBloomFilter
+///
+/// A chain of independent `BloomFilter`s over the same set of elements, where
+/// an element is only reported as present if every filter contains it.
+///
+/// Filters with independent probes produce independent false positives, so
+/// the false positive rate of the chain is the product of the rates of its
+/// filters. This improves the error rate of an existing filter that cannot be
+/// rebuilt, by chaining a second filter built from the same elements with a
+/// different salt, see `BloomFilter::optimal_salted`.
+///
+/// Every filter costs its own memory and hashing on every query. A single
+/// filter using the memory of the whole chain reaches a lower error rate, so
+/// a chain is only worthwhile when the first filter cannot be replaced.
+pub struct ChainedBloomFilter<T> {
+    filters: Vec<BloomFilter<T>>,
+}
+
+impl<T: BloomHasher> ChainedBloomFilter<T> {
+    /// Create a `ChainedBloomFilter` starting with `filter`.
+    pub fn new(filter: BloomFilter<T>) -> Self {
+        Self {
+            filters: vec![filter],
+        }
+    }
+
+    /// Append `filter` to the chain.
+    ///
+    /// `filter` must contain every element of the chain, otherwise those
+    /// elements are no longer found, and must use different probes than the
+    /// other filters, for example through a different seed offset. Otherwise
+    /// its false positives coincide with theirs and the error rate does not
+    /// improve.
+    pub fn chain(mut self, filter: BloomFilter<T>) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Insert a slice of bytes into every filter of the chain.
+    ///
+    /// Returns `true` if at least one bit of any filter was flipped, which
+    /// guarantees the element was not present before.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) -> bool {
+        let bytes = bytes.as_ref();
+        let mut new = false;
+        for filter in self.filters.iter_mut() {
+            new |= filter.insert(bytes);
+        }
+        new
+    }
+
+    /// Insert a slice of slices of bytes into every filter of the chain.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in every filter of the chain.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        let bytes = bytes.as_ref();
+        self.filters.iter().all(|filter| filter.contains(bytes))
+    }
+
+    /// Returns the filters of the chain, in the order they were chained.
+    pub fn filters(&self) -> &[BloomFilter<T>] {
+        &self.filters
+    }
+
+    /// Returns the number of filters in the chain.
+    pub fn depth(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns the number of times `insert` has been called on the first
+    /// filter of the chain.
+    pub fn len(&self) -> u64 {
+        self.filters[0].len()
+    }
+
+    /// Returns `true` if nothing has been inserted into the first filter of
+    /// the chain.
+    pub fn is_empty(&self) -> bool {
+        self.filters[0].is_empty()
+    }
+
+    /// Calculate the expected false positive rate of the chain, the product
+    /// of the expected false positive rates of its filters.
+    pub fn false_positive_rate(&self) -> f64 {
+        self.filters.iter().map(|filter| filter.false_positive_rate()).product()
+    }
+
+    /// Returns the approximate number of bytes of memory used by the filters
+    /// of the chain, see `BloomFilter::memory_bytes`.
+    pub fn memory_bytes(&self) -> usize {
+        self.filters.iter().map(|filter| filter.memory_bytes()).sum()
+    }
+}
+
+impl<T: BloomHasher + Clone> ChainedBloomFilter<T> {
+    /// Create a `ChainedBloomFilter` of `depth` empty filters, each sized by
+    /// its optimal parameters for `error_rate`, giving the chain an error rate
+    /// of `error_rate` to the power of `depth`.
+    ///
+    /// `depth` is clamped to a minimum of 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    /// See `try_optimal` for a non-panicking alternative.
+    pub fn optimal(hasher: T, max_elements: u64, error_rate: f64, depth: u32) -> Self {
+        Self::try_optimal(hasher, max_elements, error_rate, depth)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a `ChainedBloomFilter` of `depth` empty filters, returning an
+    /// error if the parameters are invalid.
+    pub fn try_optimal(
+        hasher: T,
+        max_elements: u64,
+        error_rate: f64,
+        depth: u32,
+    ) -> Result<Self, BloomError> {
+        let filters = (0..depth.max(1))
+            .map(|i| {
+                // Hashers combining two 32-bit hashes use the seeds `offset` and
+                // `offset + 1`, so consecutive seed offsets would share a seed.
+                let filter = BloomFilter::try_optimal(hasher.clone(), max_elements, error_rate)?;
+                Ok(filter.with_seed_offset(i * 2))
+            })
+            .collect::<Result<Vec<_>, BloomError>>()?;
+
+        Ok(Self { filters })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    fn measured_false_positive_rate<F: Fn(&[u8]) -> bool>(contains: F) -> f64 {
+        let false_positives = (10000..210000_u32)
+            .filter(|i| contains(&i.to_le_bytes()))
+            .count();
+        false_positives as f64 / 200000_f64
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut chained = ChainedBloomFilter::optimal(Murmur3, 1000, 0.1, 3);
+        chained.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(chained.contains(word));
+        }
+        assert_eq!(3, chained.depth());
+        assert_eq!(1000, chained.len());
+        assert_eq!(3 * chained.filters()[0].memory_bytes(), chained.memory_bytes());
+    }
+
+    #[test]
+    fn test_chained_false_positive_rate() {
+        // Chain a second filter onto an existing one with a high error rate.
+        let mut existing = BloomFilter::optimal(Murmur3, 10000, 0.1);
+        let mut second = BloomFilter::optimal_salted(Murmur3, 10000, 0.1, 2);
+        for i in 0..10000_u32 {
+            existing.insert(i.to_le_bytes());
+            second.insert(i.to_le_bytes());
+        }
+
+        let first_rate = measured_false_positive_rate(|key| existing.contains(key));
+        let second_rate = measured_false_positive_rate(|key| second.contains(key));
+
+        let chained = ChainedBloomFilter::new(existing).chain(second);
+        let chained_rate = measured_false_positive_rate(|key| chained.contains(key));
+
+        let product = first_rate * second_rate;
+        assert!((chained_rate - product).abs() < product * 0.2, "{} vs {}", chained_rate, product);
+        assert!((chained.false_positive_rate() - 0.01).abs() < 0.002);
+    }
+}
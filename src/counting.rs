@@ -0,0 +1,327 @@
+use bloom::{checked_len, optimal_parameters, Probes};
+use error::BloomError;
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+
+/// An unsigned integer type used for the counters of a `CountingBloomFilter`.
+///
+/// Wider counters saturate later, for elements inserted many times, at the
+/// cost of more memory per counter.
+pub trait Counter: Copy + PartialEq {
+    /// The zero value of the counter.
+    const ZERO: Self;
+    /// The value at which the counter saturates.
+    const MAX: Self;
+
+    /// Returns the counter incremented by one, saturating at `MAX`.
+    fn increment(self) -> Self;
+
+    /// Returns the counter decremented by one, saturating at `ZERO`.
+    fn decrement(self) -> Self;
+}
+
+macro_rules! impl_counter {
+    ($($ty:ty),*) => {
+        $(
+            impl Counter for $ty {
+                const ZERO: Self = 0;
+                const MAX: Self = <$ty>::MAX;
+
+                #[inline]
+                fn increment(self) -> Self {
+                    self.saturating_add(1)
+                }
+
+                #[inline]
+                fn decrement(self) -> Self {
+                    self.saturating_sub(1)
+                }
+            }
+        )*
+    };
+}
+
+impl_counter!(u8, u16, u32);
+
+/// CountingBloomFilter
+///
+/// A bloom filter that supports removing elements, by replacing each bit
+/// with a saturating counter.
+///
+/// Counters are `u8` by default, see `new_with_counter` for other widths.
+/// They saturate at `C::MAX` instead of wrapping. A saturated counter is
+/// never decremented again, since its true count is unknown, so removing
+/// elements can never introduce false negatives for other elements.
+///
+/// Removing an element that was never inserted decrements counters belonging
+/// to other elements and can corrupt the filter, causing false negatives.
+pub struct CountingBloomFilter<T, C = u8> {
+    hasher: T,
+    k: u32,
+    counters: Vec<C>,
+    insert_count: u64,
+}
+
+impl<T: BloomHasher> CountingBloomFilter<T> {
+    /// Create a new `CountingBloomFilter` with `u8` counters given a `hasher`,
+    /// the number of hash functions to use,
+    /// and the number of counters.
+    ///
+    /// Both `k` and `array_size` are clamped to a minimum of 1.
+    pub fn new(hasher: T, k: u32, array_size: u64) -> Self {
+        Self::new_with_counter(hasher, k, array_size)
+    }
+
+    /// Create a `CountingBloomFilter` by computing its optimal parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    /// See `try_optimal` for a non-panicking alternative.
+    pub fn optimal(hasher: T, max_elements: u64, error_rate: f64) -> Self {
+        Self::try_optimal(hasher, max_elements, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a `CountingBloomFilter` by computing its optimal parameters,
+    /// returning an error if the parameters are invalid.
+    pub fn try_optimal(hasher: T, max_elements: u64, error_rate: f64) -> Result<Self, BloomError> {
+        Self::try_optimal_with_counter(hasher, max_elements, error_rate)
+    }
+}
+
+impl<T: BloomHasher, C: Counter> CountingBloomFilter<T, C> {
+    /// Create a new `CountingBloomFilter` with counters of type `C`.
+    ///
+    /// Both `k` and `array_size` are clamped to a minimum of 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `array_size` does not fit in a `usize`, as on 32-bit targets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{CountingBloomFilter, Murmur3};
+    ///
+    /// let mut filter = CountingBloomFilter::<_, u16>::new_with_counter(Murmur3, 7, 1000);
+    /// filter.insert(b"Hello");
+    ///
+    /// assert!(filter.contains("Hello"));
+    /// ```
+    pub fn new_with_counter(hasher: T, k: u32, array_size: u64) -> Self {
+        let len = checked_len(array_size.max(1)).unwrap_or_else(|e| panic!("{}", e));
+        Self {
+            hasher,
+            k: k.max(1),
+            counters: vec![C::ZERO; len],
+            insert_count: 0,
+        }
+    }
+
+    /// Create a `CountingBloomFilter` with counters of type `C` by computing
+    /// its optimal parameters, returning an error if the parameters are
+    /// invalid.
+    pub fn try_optimal_with_counter(
+        hasher: T,
+        max_elements: u64,
+        error_rate: f64,
+    ) -> Result<Self, BloomError> {
+        let (k, m) = optimal_parameters(max_elements, error_rate)?;
+        Ok(Self::new_with_counter(hasher, k, m))
+    }
+
+    /// Insert a slice of bytes into the `CountingBloomFilter`.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        for index in self.probes(bytes.as_ref()) {
+            self.counters[index] = self.counters[index].increment();
+        }
+        self.insert_count += 1;
+    }
+
+    /// Insert a slice of slices of bytes into the `CountingBloomFilter`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Remove a slice of bytes from the `CountingBloomFilter`.
+    ///
+    /// The element must have been inserted before, otherwise the filter may
+    /// start returning false negatives.
+    pub fn remove<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        for index in self.probes(bytes.as_ref()) {
+            let counter = self.counters[index];
+            if counter != C::MAX {
+                self.counters[index] = counter.decrement();
+            }
+        }
+        self.insert_count = self.insert_count.saturating_sub(1);
+    }
+
+    /// Remove a slice of slices of bytes from the `CountingBloomFilter`.
+    ///
+    /// Counters never go below zero and saturated counters are left alone, as
+    /// in `remove`. Every element must have been inserted before, since
+    /// removing elements that were never inserted corrupts the counts of other
+    /// elements and causes false negatives.
+    pub fn remove_all<B: AsRef<[u8]>>(&mut self, items: &[B]) {
+        for item in items {
+            self.remove(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the `CountingBloomFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative, as long as only inserted elements are removed.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.probes(bytes.as_ref()).all(|index| self.counters[index] != C::ZERO)
+    }
+
+    /// Returns the number of elements inserted and not yet removed.
+    pub fn len(&self) -> u64 {
+        self.insert_count
+    }
+
+    /// Returns `true` if the `CountingBloomFilter` contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.insert_count == 0
+    }
+
+    /// Returns the `k` counter indices for `bytes`.
+    fn probes(&self, bytes: &[u8]) -> Probes {
+        Probes::new(&self.hasher, 0, self.k, self.counters.len(), bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_insert_remove() {
+        let mut filter = CountingBloomFilter::optimal(Murmur3, 100, 0.01);
+        filter.insert_all(&["apple", "banana"]);
+        assert!(filter.contains("apple"));
+        assert!(filter.contains("banana"));
+        assert_eq!(2, filter.len());
+
+        filter.remove(b"apple");
+        assert!(!filter.contains("apple"));
+        assert!(filter.contains("banana"));
+        assert_eq!(1, filter.len());
+
+        filter.remove(b"banana");
+        assert!(!filter.contains("banana"));
+        assert!(filter.is_empty());
+        assert!(filter.counters.iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn test_insert_remove_as_ref() {
+        let mut filter = CountingBloomFilter::optimal(Murmur3, 100, 0.01);
+        let word = String::from("apple");
+        filter.insert(&word);
+        filter.insert("banana");
+        filter.insert(vec![1_u8, 2, 3]);
+        assert!(filter.contains(&word));
+        assert!(filter.contains([1_u8, 2, 3]));
+
+        filter.remove(word);
+        filter.remove(vec![1_u8, 2, 3]);
+        assert!(!filter.contains("apple"));
+        assert!(filter.contains("banana"));
+        assert_eq!(1, filter.len());
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let words: Vec<String> = (0..100).map(|i| format!("word{}", i)).collect();
+        let mut filter = CountingBloomFilter::optimal(Murmur3, 100, 0.01);
+        filter.insert_all(&words);
+
+        filter.remove_all(&words[..50]);
+        assert_eq!(50, filter.len());
+
+        // Absent is not guaranteed for removed elements, since the counters of
+        // the remaining elements may cover them, but it holds for almost all.
+        let present = words[..50].iter().filter(|word| filter.contains(word)).count();
+        assert!(present <= 2, "{} removed words still present", present);
+        for word in words[50..].iter() {
+            assert!(filter.contains(word));
+        }
+
+        filter.remove_all(&words[50..]);
+        assert!(filter.is_empty());
+        assert!(filter.counters.iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn test_duplicate_insert() {
+        let mut filter = CountingBloomFilter::optimal(Murmur3, 100, 0.01);
+        filter.insert(b"apple");
+        filter.insert(b"apple");
+
+        filter.remove(b"apple");
+        assert!(filter.contains("apple"));
+
+        filter.remove(b"apple");
+        assert!(!filter.contains("apple"));
+    }
+
+    #[test]
+    fn test_saturation() {
+        let mut filter = CountingBloomFilter::new(Murmur3, 3, 100);
+        for _ in 0..300 {
+            filter.insert(b"apple");
+        }
+        assert!(filter.counters.iter().all(|&c| c == 0 || c == u8::MAX));
+
+        for _ in 0..300 {
+            filter.remove(b"apple");
+        }
+        assert!(filter.contains("apple"));
+    }
+
+    #[test]
+    fn test_saturation_u16() {
+        let mut filter = CountingBloomFilter::<_, u16>::new_with_counter(Murmur3, 3, 100);
+        for _ in 0..70000 {
+            filter.insert(b"apple");
+        }
+        assert!(filter.counters.iter().all(|&c| c == 0 || c == u16::MAX));
+
+        for _ in 0..70000 {
+            filter.remove(b"apple");
+        }
+        assert!(filter.contains("apple"));
+    }
+
+    #[test]
+    fn test_saturation_u32() {
+        let mut filter = CountingBloomFilter::<_, u32>::new_with_counter(Murmur3, 3, 100);
+        let index = filter.probes(b"apple").next().unwrap();
+        filter.counters[index] = u32::MAX - 1;
+
+        filter.insert(b"apple");
+        filter.insert(b"apple");
+        assert_eq!(u32::MAX, filter.counters[index]);
+
+        filter.remove(b"apple");
+        assert_eq!(u32::MAX, filter.counters[index]);
+        assert!(filter.contains("apple"));
+    }
+
+    #[test]
+    fn test_counter() {
+        assert_eq!(u8::MAX, u8::MAX.increment());
+        assert_eq!(0, 0_u16.decrement());
+        assert_eq!(2, 1_u32.increment());
+        assert_eq!(0, 1_u8.decrement());
+    }
+}
@@ -0,0 +1,331 @@
+use bloom::checked_len;
+use error::BloomError;
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+use core::mem;
+
+/// The number of fingerprints stored in each bucket.
+const BUCKET_SIZE: usize = 4;
+
+/// The maximum number of fingerprints evicted while inserting an element,
+/// before the table is considered full.
+const MAX_KICKS: usize = 500;
+
+/// A bucket of fingerprints, where a fingerprint of zero marks an empty slot.
+#[derive(Clone, Copy)]
+struct Bucket([u16; BUCKET_SIZE]);
+
+impl Bucket {
+    #[inline]
+    fn contains(&self, fingerprint: u16) -> bool {
+        self.0.contains(&fingerprint)
+    }
+
+    /// Store `fingerprint` in an empty slot, returning `false` if the bucket
+    /// is full.
+    #[inline]
+    fn insert(&mut self, fingerprint: u16) -> bool {
+        match self.0.iter_mut().find(|slot| **slot == 0) {
+            Some(slot) => {
+                *slot = fingerprint;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a single copy of `fingerprint`, returning `false` if it was not
+    /// present.
+    #[inline]
+    fn remove(&mut self, fingerprint: u16) -> bool {
+        match self.0.iter_mut().find(|slot| **slot == fingerprint) {
+            Some(slot) => {
+                *slot = 0;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// CuckooFilter
+///
+/// A probabilistic set storing a 16-bit fingerprint of each element in one of
+/// two candidate buckets, supporting removal without the memory overhead of a
+/// `CountingBloomFilter`.
+///
+/// The second bucket of an element is derived from its first bucket and its
+/// fingerprint alone, so fingerprints can be moved between their two buckets
+/// to make room for new elements. The false positive rate is roughly
+/// `8 / 2^16`, about 0.012%, independent of how full the filter is.
+///
+/// Removing an element that was never inserted can remove the fingerprint of
+/// another element, causing false negatives.
+#[derive(Clone)]
+pub struct CuckooFilter<T> {
+    hasher: T,
+    buckets: Vec<Bucket>,
+    victim: Option<(usize, u16)>,
+    len: u64,
+}
+
+impl<T: BloomHasher> CuckooFilter<T> {
+    /// Create a new `CuckooFilter` given a `hasher` and the number of
+    /// fingerprints it should hold.
+    ///
+    /// The number of buckets is rounded up to a power of two. Inserts usually
+    /// start failing once around 95% of the slots are occupied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of buckets does not fit in a `usize`, as on 32-bit
+    /// targets.
+    pub fn new(hasher: T, capacity: u64) -> Self {
+        let num_buckets = capacity
+            .div_ceil(BUCKET_SIZE as u64)
+            .max(1)
+            .checked_next_power_of_two()
+            .ok_or(BloomError::CapacityTooLarge)
+            .and_then(checked_len)
+            .unwrap_or_else(|e| panic!("{}", e));
+        Self {
+            hasher,
+            buckets: vec![Bucket([0; BUCKET_SIZE]); num_buckets],
+            victim: None,
+            len: 0,
+        }
+    }
+
+    /// Insert a slice of bytes into the `CuckooFilter`.
+    ///
+    /// Returns `false` if the filter is full, in which case it is left
+    /// unchanged. Inserting the same element more than once stores several
+    /// copies of its fingerprint, so it must be removed as many times.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) -> bool {
+        if self.victim.is_some() {
+            return false;
+        }
+
+        let (index, fingerprint) = self.locate(bytes.as_ref());
+        if !self.buckets[index].insert(fingerprint) {
+            let alt_index = self.alt_index(index, fingerprint);
+            self.place(alt_index, fingerprint);
+        }
+        self.len += 1;
+        true
+    }
+
+    /// Insert a slice of slices of bytes into the `CuckooFilter`.
+    ///
+    /// Returns `false` if the filter became full, in which case the remaining
+    /// items are not inserted.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) -> bool {
+        slice.iter().all(|item| self.insert(item))
+    }
+
+    /// Remove a slice of bytes from the `CuckooFilter`.
+    ///
+    /// Returns `false` if no matching fingerprint was found. The element must
+    /// have been inserted before, otherwise the filter may start returning
+    /// false negatives.
+    pub fn remove<B: AsRef<[u8]>>(&mut self, bytes: B) -> bool {
+        let (index, fingerprint) = self.locate(bytes.as_ref());
+        let alt_index = self.alt_index(index, fingerprint);
+
+        let removed = if self.victim_matches(index, alt_index, fingerprint) {
+            self.victim = None;
+            true
+        } else {
+            self.buckets[index].remove(fingerprint) || self.buckets[alt_index].remove(fingerprint)
+        };
+        if !removed {
+            return false;
+        }
+
+        self.len -= 1;
+
+        // A slot has been freed, so try to find room for the evicted fingerprint.
+        if let Some((index, fingerprint)) = self.victim.take() {
+            self.place(index, fingerprint);
+        }
+        true
+    }
+
+    /// Check whether a slice of bytes exists in the `CuckooFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative, as long as only inserted elements are removed.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        let (index, fingerprint) = self.locate(bytes.as_ref());
+        let alt_index = self.alt_index(index, fingerprint);
+
+        self.buckets[index].contains(fingerprint)
+            || self.buckets[alt_index].contains(fingerprint)
+            || self.victim_matches(index, alt_index, fingerprint)
+    }
+
+    /// Returns the total number of fingerprints the `CuckooFilter` has room for.
+    pub fn capacity(&self) -> u64 {
+        (self.buckets.len() * BUCKET_SIZE) as u64
+    }
+
+    /// Returns the number of elements inserted and not yet removed.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the `CuckooFilter` contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Hash `bytes` once, returning the index of its first bucket from the low
+    /// bits and its non-zero fingerprint from the high bits.
+    #[inline]
+    fn locate(&self, bytes: &[u8]) -> (usize, u16) {
+        let hash = self.hasher.hash64(0, bytes);
+        let index = hash as usize & (self.buckets.len() - 1);
+        let fingerprint = ((hash >> 48) as u16).max(1);
+        (index, fingerprint)
+    }
+
+    /// Returns the other bucket of a fingerprint stored in bucket `index`.
+    ///
+    /// This is an involution, so applying it to either bucket of an element
+    /// returns the other one.
+    #[inline]
+    fn alt_index(&self, index: usize, fingerprint: u16) -> usize {
+        let hash = self.hasher.hash(0, &fingerprint.to_le_bytes());
+        (index ^ hash as usize) & (self.buckets.len() - 1)
+    }
+
+    /// Store `fingerprint` in bucket `index`, evicting fingerprints into their
+    /// other bucket until a free slot is found.
+    ///
+    /// If no free slot is found after `MAX_KICKS` evictions, the last evicted
+    /// fingerprint is kept aside as the victim, which marks the filter as full.
+    fn place(&mut self, mut index: usize, mut fingerprint: u16) {
+        for kick in 0..MAX_KICKS {
+            if self.buckets[index].insert(fingerprint) {
+                return;
+            }
+
+            mem::swap(&mut fingerprint, &mut self.buckets[index].0[kick % BUCKET_SIZE]);
+            index = self.alt_index(index, fingerprint);
+        }
+
+        if !self.buckets[index].insert(fingerprint) {
+            self.victim = Some((index, fingerprint));
+        }
+    }
+
+    /// Check whether the victim is `fingerprint` stored in either bucket.
+    #[inline]
+    fn victim_matches(&self, index: usize, alt_index: usize, fingerprint: u16) -> bool {
+        match self.victim {
+            Some((victim_index, victim_fingerprint)) => {
+                victim_fingerprint == fingerprint
+                    && (victim_index == index || victim_index == alt_index)
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_insert_remove_as_ref() {
+        let mut filter = CuckooFilter::new(Murmur3, 100);
+        let word = String::from("apple");
+        assert!(filter.insert(&word));
+        assert!(filter.insert("banana"));
+        assert!(filter.insert(vec![1_u8, 2, 3]));
+        assert!(filter.contains(&word));
+        assert!(filter.contains([1_u8, 2, 3]));
+
+        assert!(filter.remove(word));
+        assert!(filter.remove(vec![1_u8, 2, 3]));
+        assert!(filter.contains("banana"));
+        assert_eq!(1, filter.len());
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut filter = CuckooFilter::new(Murmur3, 100);
+        assert!(filter.insert_all(&["apple", "banana"]));
+        assert!(filter.contains("apple"));
+        assert!(filter.contains("banana"));
+        assert_eq!(2, filter.len());
+
+        assert!(filter.remove(b"apple"));
+        assert!(!filter.contains("apple"));
+        assert!(filter.contains("banana"));
+        assert!(!filter.remove(b"apple"));
+        assert_eq!(1, filter.len());
+
+        assert!(filter.remove(b"banana"));
+        assert!(!filter.contains("banana"));
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let words: Vec<String> = (0..10000).map(|i| format!("word{}", i)).collect();
+
+        let mut filter = CuckooFilter::new(Murmur3, 11000);
+        assert!(filter.insert_all(&words));
+
+        for word in words.iter() {
+            assert!(filter.contains(word));
+        }
+        for word in words.iter().step_by(2) {
+            assert!(filter.remove(word.as_bytes()));
+        }
+        for word in words.iter().skip(1).step_by(2) {
+            assert!(filter.contains(word));
+        }
+        assert_eq!(5000, filter.len());
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let mut filter = CuckooFilter::new(Murmur3, 11000);
+        for i in 0..10000_u32 {
+            filter.insert(i.to_le_bytes());
+        }
+
+        let false_positives = (10000..110000_u32)
+            .filter(|i| filter.contains(i.to_le_bytes()))
+            .count();
+        assert!((false_positives as f64 / 100000_f64) < 0.001);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut filter = CuckooFilter::new(Murmur3, 64);
+        let inserted: Vec<u32> = (0..1000_u32)
+            .take_while(|i| filter.insert(i.to_le_bytes()))
+            .collect();
+
+        // Every slot may be occupied, with one more fingerprint kept aside.
+        assert!(inserted.len() as u64 <= filter.capacity() + 1);
+        assert_eq!(inserted.len() as u64, filter.len());
+        for i in inserted.iter() {
+            assert!(filter.contains(i.to_le_bytes()));
+        }
+
+        // The filter stays full until an element is removed.
+        assert!(!filter.insert(b"apple"));
+        assert!(filter.remove(inserted[0].to_le_bytes()));
+        assert!(filter.insert(b"apple"));
+        for i in inserted[1..].iter() {
+            assert!(filter.contains(i.to_le_bytes()));
+        }
+        assert!(filter.contains("apple"));
+    }
+}
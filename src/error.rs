@@ -0,0 +1,63 @@
+use core::error::Error;
+use core::fmt;
+
+/// An error produced when constructing or combining bloom filters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BloomError {
+    /// The error rate was not within the open range `(0, 1)`.
+    InvalidErrorRate(f64),
+    /// The expected number of elements was zero.
+    ZeroElements,
+    /// The number of hash functions was zero.
+    ZeroHashFunctions,
+    /// The size of the bit array was zero.
+    ZeroArraySize,
+    /// The computed size of the bit array is too large to allocate.
+    CapacityTooLarge,
+    /// A `BloomFilterBuilder` was given parameters that contradict each other.
+    ConflictingParameters,
+    /// A `BloomFilterBuilder` was not given enough parameters to size the
+    /// filter.
+    MissingParameters,
+    /// Two filters were combined that do not share the same number of hash
+    /// functions and array size.
+    IncompatibleDimensions,
+    /// Serialized data was truncated or not in the expected format.
+    CorruptData,
+    /// The growth factor was not a finite number of at least 1.
+    InvalidGrowthFactor(f64),
+    /// An empty list of filters was given to combine.
+    NoFilters,
+    /// A filter could not be rebuilt from its recorded elements, because it
+    /// also holds elements that were not recorded.
+    UnrecordedElements,
+}
+
+impl fmt::Display for BloomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BloomError::InvalidErrorRate(rate) => {
+                write!(f, "error rate must be 0 < error_rate < 1, got {}", rate)
+            }
+            BloomError::ZeroElements => write!(f, "expected number of elements must be non-zero"),
+            BloomError::ZeroHashFunctions => write!(f, "number of hash functions must be non-zero"),
+            BloomError::ZeroArraySize => write!(f, "array size must be non-zero"),
+            BloomError::CapacityTooLarge => write!(f, "array size is too large to allocate"),
+            BloomError::ConflictingParameters => write!(f, "conflicting parameters were given"),
+            BloomError::MissingParameters => write!(f, "not enough parameters were given"),
+            BloomError::IncompatibleDimensions => {
+                write!(f, "filters must have the same number of hash functions and array size")
+            }
+            BloomError::CorruptData => write!(f, "serialized data is corrupt or truncated"),
+            BloomError::InvalidGrowthFactor(factor) => {
+                write!(f, "growth factor must be finite and at least 1, got {}", factor)
+            }
+            BloomError::NoFilters => write!(f, "at least one filter must be given"),
+            BloomError::UnrecordedElements => {
+                write!(f, "filter holds elements that were not recorded")
+            }
+        }
+    }
+}
+
+impl Error for BloomError {}
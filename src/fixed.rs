@@ -0,0 +1,179 @@
+use bit_vec::BitVec;
+
+use bloom::{array_size_for_k, checked_len, fastrange};
+use error::BloomError;
+use hash::BloomHasher;
+
+/// BloomFilterK
+///
+/// A bloom filter whose number of hash functions `K` is fixed at compile time.
+///
+/// With `K` known, the probe loop is fully unrolled and all `K` indices are
+/// computed before any bit is read, so the memory accesses can overlap. The
+/// bits are set exactly as a `BloomFilter` with `K` hash functions and the
+/// same array size would set them.
+///
+/// The tradeoff is flexibility: `K` is part of the type, so filters with a `K`
+/// chosen at runtime, for example by `optimal`, need a `BloomFilter` instead.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bloom_filter_rs as bloom_filter;
+///
+/// use bloom_filter::{BloomFilterK, Murmur3};
+///
+/// let mut bloom_filter = BloomFilterK::<8, _>::optimal(Murmur3, 1000, 0.01);
+///
+/// bloom_filter.insert("Hello");
+///
+/// assert!(bloom_filter.contains("Hello"));
+/// ```
+#[derive(Clone)]
+pub struct BloomFilterK<const K: usize, T> {
+    hasher: T,
+    bit_vec: BitVec,
+    insert_count: u64,
+}
+
+impl<const K: usize, T: BloomHasher> BloomFilterK<K, T> {
+    /// Create a new `BloomFilterK` given a `hasher` and the size of the
+    /// underlying bit array.
+    ///
+    /// `array_size` is clamped to a minimum of 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `K` is zero, or if `array_size` does not fit in a `usize`, as
+    /// on 32-bit targets.
+    pub fn new(hasher: T, array_size: u64) -> Self {
+        if K == 0 {
+            panic!("{}", BloomError::ZeroHashFunctions);
+        }
+
+        let len = checked_len(array_size.max(1)).unwrap_or_else(|e| panic!("{}", e));
+        Self {
+            hasher,
+            bit_vec: BitVec::from_elem(len, false),
+            insert_count: 0,
+        }
+    }
+
+    /// Create the smallest `BloomFilterK` whose error rate stays below
+    /// `error_rate` for `max_elements` elements, as `BloomFilter::optimal_with_k`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)`, `max_elements` is zero or
+    /// `K` is zero. See `try_optimal` for a non-panicking alternative.
+    pub fn optimal(hasher: T, max_elements: u64, error_rate: f64) -> Self {
+        Self::try_optimal(hasher, max_elements, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create the smallest `BloomFilterK` whose error rate stays below
+    /// `error_rate` for `max_elements` elements, returning an error if the
+    /// parameters are invalid.
+    pub fn try_optimal(hasher: T, max_elements: u64, error_rate: f64) -> Result<Self, BloomError> {
+        let m = array_size_for_k(max_elements, error_rate, K as u32)?;
+        Ok(Self::new(hasher, m))
+    }
+
+    /// Insert a slice of bytes into the `BloomFilterK`.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        for index in self.indices(bytes.as_ref()).iter() {
+            self.bit_vec.set(*index, true);
+        }
+        self.insert_count += 1;
+    }
+
+    /// Insert a slice of slices of bytes into the `BloomFilterK`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the `BloomFilterK`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.indices(bytes.as_ref()).iter().all(|&index| self.bit_vec[index])
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        K as u32
+    }
+
+    /// Returns the size of the underlying bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.bit_vec.len() as u64
+    }
+
+    /// Returns the number of times `insert` has been called.
+    pub fn len(&self) -> u64 {
+        self.insert_count
+    }
+
+    /// Returns `true` if nothing has been inserted into the `BloomFilterK`.
+    pub fn is_empty(&self) -> bool {
+        self.insert_count == 0
+    }
+
+    /// Returns the `K` bit indices for `bytes`, derived from a single hash as
+    /// by `BloomFilter`.
+    #[inline]
+    fn indices(&self, bytes: &[u8]) -> [usize; K] {
+        let hash = self.hasher.hash128(0, bytes);
+        let (h1, h2) = (hash as u64, (hash >> 64) as u64);
+        let len = self.bit_vec.len();
+
+        let mut indices = [0; K];
+        for (i, index) in indices.iter_mut().enumerate() {
+            *index = fastrange(h1.wrapping_add((i as u64).wrapping_mul(h2)), len);
+        }
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bloom::BloomFilter;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_no_false_negatives() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+
+        let mut filter = BloomFilterK::<7, _>::optimal(Murmur3, 1000, 0.01);
+        filter.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(filter.contains(word));
+        }
+        assert_eq!(7, filter.num_hashes());
+        assert_eq!(1000, filter.len());
+    }
+
+    #[test]
+    fn test_matches_bloom_filter() {
+        let mut fixed = BloomFilterK::<8, _>::new(Murmur3, 10000);
+        let mut bloom_filter = BloomFilter::new(Murmur3, 8, 10000);
+        for i in 0..1000_u32 {
+            fixed.insert(i.to_le_bytes());
+            bloom_filter.insert(i.to_le_bytes());
+        }
+
+        assert_eq!(bloom_filter.bit_vec(), &fixed.bit_vec);
+    }
+
+    #[test]
+    fn test_zero_hash_functions() {
+        assert_eq!(
+            Some(BloomError::ZeroHashFunctions),
+            BloomFilterK::<0, _>::try_optimal(Murmur3, 1000, 0.01).err()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use bloom::BloomFilter;
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+/// FrozenBloomFilter
+///
+/// A read-only `BloomFilter`, created by `BloomFilter::freeze`.
+///
+/// A frozen filter can be queried and serialized, but exposes no way to insert
+/// into or clear it, so a filter shared with query services cannot be modified
+/// by accident. Use `thaw` to get the mutable `BloomFilter` back.
+///
+/// ```compile_fail
+/// extern crate bloom_filter_rs as bloom_filter;
+///
+/// use bloom_filter::{BloomFilter, Murmur3};
+///
+/// let mut frozen = BloomFilter::optimal(Murmur3, 100, 0.01).freeze();
+/// frozen.insert(b"Hello");
+/// ```
+#[derive(Clone)]
+pub struct FrozenBloomFilter<T> {
+    bloom_filter: BloomFilter<T>,
+}
+
+impl<T: BloomHasher> FrozenBloomFilter<T> {
+    /// Freeze `bloom_filter`, see `BloomFilter::freeze`.
+    pub(crate) fn new(bloom_filter: BloomFilter<T>) -> Self {
+        Self { bloom_filter }
+    }
+
+    /// Check whether a slice of bytes exists in the `FrozenBloomFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.bloom_filter.contains(bytes)
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.bloom_filter.num_hashes()
+    }
+
+    /// Returns the size of the underlying bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.bloom_filter.capacity_bits()
+    }
+
+    /// Returns the number of times `insert` was called before freezing.
+    pub fn len(&self) -> u64 {
+        self.bloom_filter.len()
+    }
+
+    /// Returns `true` if nothing was inserted before freezing.
+    pub fn is_empty(&self) -> bool {
+        self.bloom_filter.is_empty()
+    }
+
+    /// Returns the fraction of bits set in the underlying bit array.
+    pub fn fill_ratio(&self) -> f64 {
+        self.bloom_filter.fill_ratio()
+    }
+
+    /// Calculate the expected false positive rate of the `FrozenBloomFilter`.
+    pub fn false_positive_rate(&self) -> f64 {
+        self.bloom_filter.false_positive_rate()
+    }
+
+    /// Serialize the `FrozenBloomFilter` in the format of `BloomFilter::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bloom_filter.to_bytes()
+    }
+
+    /// Write the `FrozenBloomFilter` to `writer` in the format of
+    /// `BloomFilter::to_bytes`.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.bloom_filter.write_to(writer)
+    }
+
+    /// Unfreeze the `FrozenBloomFilter`, returning the mutable `BloomFilter`.
+    pub fn thaw(self) -> BloomFilter<T> {
+        self.bloom_filter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_freeze_thaw() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        bloom_filter.insert_all(&["apple", "banana"]);
+
+        let frozen = bloom_filter.clone().freeze();
+        assert!(frozen.contains("apple"));
+        assert!(frozen.contains("banana"));
+        assert_eq!(2, frozen.len());
+        assert_eq!(bloom_filter.fill_ratio(), frozen.fill_ratio());
+        assert_eq!(bloom_filter.to_bytes(), frozen.to_bytes());
+
+        let mut thawed = frozen.thaw();
+        assert_eq!(bloom_filter, thawed);
+
+        thawed.insert(b"cherry");
+        assert!(thawed.contains("cherry"));
+    }
+}
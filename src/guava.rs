@@ -0,0 +1,317 @@
+use bit_vec::BitVec;
+
+use bloom::{checked_len, optimal_parameters};
+use error::BloomError;
+use hash::murmur3_x64_128;
+
+use alloc::vec::Vec;
+
+/// The ordinal of the `MURMUR128_MITZ_64` strategy in Guava's
+/// `BloomFilterStrategies`.
+const MURMUR128_MITZ_64: u8 = 1;
+
+/// The length of the strategy, hash function count and data length fields.
+const HEADER_LEN: usize = 6;
+
+/// GuavaBloomFilter
+///
+/// A bloom filter using the probing and bit layout of Guava's `BloomFilter`
+/// with the default `MURMUR128_MITZ_64` strategy, for sharing filters with JVM
+/// services.
+///
+/// Guava hashes each element with the 128-bit x64 variant of murmur3 with a
+/// seed of zero, and derives index `i` as `(h1 + i * h2) mod m` with the sign
+/// bit cleared. This differs from the probing of `BloomFilter`, so the two
+/// cannot share bit arrays. The bits are stored in 64-bit words, so the array
+/// size is always a multiple of 64.
+///
+/// Elements are hashed as raw bytes, which matches a Guava filter using
+/// `Funnels.byteArrayFunnel()`, or `Funnels.stringFunnel(UTF_8)` for strings.
+/// Other funnels write a different byte stream and will not agree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuavaBloomFilter {
+    k: u32,
+    bit_vec: BitVec,
+    insert_count: u64,
+}
+
+impl GuavaBloomFilter {
+    /// Create a new `GuavaBloomFilter` given the number of hash functions to
+    /// use and the size of the underlying bit array.
+    ///
+    /// `k` is clamped to `[1, 255]`, since Guava stores it in a single byte,
+    /// and `array_size` is rounded up to a non-zero multiple of 64.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rounded `array_size` is more than `i32::MAX` words, which
+    /// Guava cannot represent, or does not fit in a `usize`, as on 32-bit
+    /// targets.
+    pub fn new(k: u32, array_size: u64) -> Self {
+        let len = bit_len(array_size).unwrap_or_else(|e| panic!("{}", e));
+
+        Self {
+            k: k.clamp(1, u8::MAX as u32),
+            bit_vec: BitVec::from_elem(len, false),
+            insert_count: 0,
+        }
+    }
+
+    /// Create a `GuavaBloomFilter` by computing its optimal parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    /// See `try_optimal` for a non-panicking alternative.
+    pub fn optimal(max_elements: u64, error_rate: f64) -> Self {
+        Self::try_optimal(max_elements, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a `GuavaBloomFilter` by computing its optimal parameters,
+    /// returning an error if the parameters are invalid.
+    pub fn try_optimal(max_elements: u64, error_rate: f64) -> Result<Self, BloomError> {
+        let (k, m) = optimal_parameters(max_elements, error_rate)?;
+        bit_len(m)?;
+
+        Ok(Self::new(k, m))
+    }
+
+    /// Deserialize a filter written by Guava's `BloomFilter.writeTo`.
+    ///
+    /// The format is the strategy ordinal and number of hash functions as
+    /// single bytes, followed by the number of 64-bit words as a big-endian
+    /// `int` and the words themselves as big-endian `long`s.
+    ///
+    /// Returns `BloomError::CorruptData` if the strategy is not
+    /// `MURMUR128_MITZ_64`, if the number of hash functions or words is zero,
+    /// or if `data` is truncated or has trailing bytes.
+    ///
+    /// Guava does not serialize the number of inserted elements, so `len` of
+    /// the result is zero.
+    pub fn from_guava_bytes(data: &[u8]) -> Result<Self, BloomError> {
+        if data.len() < HEADER_LEN || data[0] != MURMUR128_MITZ_64 || data[1] == 0 {
+            return Err(BloomError::CorruptData);
+        }
+
+        let mut len = [0; 4];
+        len.copy_from_slice(&data[2..HEADER_LEN]);
+        let words = i32::from_be_bytes(len);
+
+        let body = &data[HEADER_LEN..];
+        if words <= 0 || body.len() as u64 != words as u64 * 8 {
+            return Err(BloomError::CorruptData);
+        }
+
+        let mut bit_vec = BitVec::from_elem(body.len() * 8, false);
+        for (i, chunk) in body.chunks_exact(8).enumerate() {
+            let mut buf = [0; 8];
+            buf.copy_from_slice(chunk);
+            let mut word = u64::from_be_bytes(buf);
+            while word != 0 {
+                bit_vec.set(i * 64 + word.trailing_zeros() as usize, true);
+                word &= word - 1;
+            }
+        }
+
+        Ok(Self {
+            k: data[1] as u32,
+            bit_vec,
+            insert_count: 0,
+        })
+    }
+
+    /// Serialize the `GuavaBloomFilter` in the format of Guava's
+    /// `BloomFilter.writeTo`, readable by `BloomFilter.readFrom`.
+    pub fn to_guava_bytes(&self) -> Vec<u8> {
+        let words = self.bit_vec.len() / 64;
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + words * 8);
+        bytes.push(MURMUR128_MITZ_64);
+        bytes.push(self.k as u8);
+        bytes.extend_from_slice(&(words as i32).to_be_bytes());
+        for pair in self.bit_vec.storage().chunks(2) {
+            let word = pair[0] as u64 | (*pair.get(1).unwrap_or(&0) as u64) << 32;
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Insert a slice of bytes into the `GuavaBloomFilter`.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        for index in self.indices(bytes.as_ref()) {
+            self.bit_vec.set(index, true);
+        }
+        self.insert_count += 1;
+    }
+
+    /// Insert a slice of slices of bytes into the `GuavaBloomFilter`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the `GuavaBloomFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.indices(bytes.as_ref()).all(|index| self.bit_vec[index])
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the size of the underlying bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.bit_vec.len() as u64
+    }
+
+    /// Returns the number of times `insert` has been called.
+    pub fn len(&self) -> u64 {
+        self.insert_count
+    }
+
+    /// Returns `true` if nothing has been inserted into the `GuavaBloomFilter`.
+    pub fn is_empty(&self) -> bool {
+        self.insert_count == 0
+    }
+
+    /// Returns the bit indices of `bytes`, as computed by `MURMUR128_MITZ_64`.
+    fn indices(&self, bytes: &[u8]) -> impl Iterator<Item = usize> {
+        let (hash1, hash2) = murmur3_x64_128(0, bytes);
+        let bit_size = self.bit_vec.len() as u64;
+
+        (0..self.k as u64).map(move |i| {
+            let combined = hash1.wrapping_add(i.wrapping_mul(hash2));
+            ((combined & i64::MAX as u64) % bit_size) as usize
+        })
+    }
+}
+
+/// Returns the length of the bit array for `array_size` bits, rounded up to a
+/// non-zero multiple of 64, or `BloomError::CapacityTooLarge` if the number
+/// of words does not fit in the `int` length of Guava's format.
+fn bit_len(array_size: u64) -> Result<usize, BloomError> {
+    let words = array_size.div_ceil(64).max(1);
+    if words > i32::MAX as u64 {
+        return Err(BloomError::CapacityTooLarge);
+    }
+
+    checked_len(words * 64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_false_negatives() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut bloom_filter = GuavaBloomFilter::optimal(1000, 0.01);
+        bloom_filter.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(bloom_filter.contains(word));
+        }
+        assert_eq!(0, bloom_filter.capacity_bits() % 64);
+    }
+
+    #[test]
+    fn test_indices() {
+        // The first index is h1 with the sign bit cleared, and every following
+        // index adds h2.
+        let bloom_filter = GuavaBloomFilter::new(3, 6400);
+        let (h1, h2) = (0xcbd8_a7b3_41bd_9b02_u64, 0x5b1e_906a_48ae_1d19_u64);
+
+        let expected: Vec<usize> = [h1, h1.wrapping_add(h2), h1.wrapping_add(h2.wrapping_mul(2))]
+            .iter()
+            .map(|h| ((h & i64::MAX as u64) % 6400) as usize)
+            .collect();
+        assert_eq!(expected, bloom_filter.indices(b"hello").collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_from_guava_bytes() {
+        // Two words, with bits 0 and 63 of the first word and bit 1 of the
+        // second word set.
+        let mut data = vec![1, 5, 0, 0, 0, 2];
+        data.extend_from_slice(&0x8000_0000_0000_0001_u64.to_be_bytes());
+        data.extend_from_slice(&2_u64.to_be_bytes());
+
+        let bloom_filter = GuavaBloomFilter::from_guava_bytes(&data).unwrap();
+        assert_eq!(5, bloom_filter.num_hashes());
+        assert_eq!(128, bloom_filter.capacity_bits());
+
+        let set: Vec<usize> = (0..128).filter(|&i| bloom_filter.bit_vec[i]).collect();
+        assert_eq!(vec![0, 63, 65], set);
+        assert_eq!(data, bloom_filter.to_guava_bytes());
+    }
+
+    #[test]
+    fn test_guava_bytes_round_trip() {
+        let mut bloom_filter = GuavaBloomFilter::new(7, 1000);
+        bloom_filter.insert_all(&["apple", "banana", "cherry"]);
+
+        let deserialized = GuavaBloomFilter::from_guava_bytes(&bloom_filter.to_guava_bytes()).unwrap();
+        assert_eq!(bloom_filter.bit_vec, deserialized.bit_vec);
+        assert!(deserialized.contains("banana"));
+    }
+
+    #[test]
+    fn test_from_guava_bytes_invalid() {
+        let bytes = GuavaBloomFilter::new(7, 1000).to_guava_bytes();
+
+        let mut bad_strategy = bytes.clone();
+        bad_strategy[0] = 0;
+        let mut zero_hashes = bytes.clone();
+        zero_hashes[1] = 0;
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let truncated = bytes[..bytes.len() - 1].to_vec();
+
+        for data in [bad_strategy, zero_hashes, trailing, truncated].iter() {
+            assert_eq!(Some(BloomError::CorruptData), GuavaBloomFilter::from_guava_bytes(data).err());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "array size is too large to allocate")]
+    fn test_new_overflow() {
+        GuavaBloomFilter::new(7, u64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "array size is too large to allocate")]
+    fn test_new_too_many_words() {
+        GuavaBloomFilter::new(7, (i32::MAX as u64 + 1) * 64);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_guava_fixture() {
+        use std::fs::{self, File};
+        use std::io::{BufRead, BufReader};
+
+        // Written by Guava's `BloomFilter.writeTo` for a filter created with
+        // `Funnels.stringFunnel(UTF_8)`, 1000 expected insertions and an error
+        // rate of 0.01, after `put` of every line of `1000.txt`.
+        let bytes = fs::read("./resources/guava_1000.bin").unwrap();
+        let words: Vec<String> = BufReader::new(File::open("./resources/1000.txt").unwrap())
+            .lines()
+            .map(|s| s.unwrap())
+            .collect();
+
+        let guava = GuavaBloomFilter::from_guava_bytes(&bytes).unwrap();
+        for word in words.iter() {
+            assert!(guava.contains(word));
+        }
+
+        // The same parameters and elements set exactly the same bits.
+        let mut bloom_filter = GuavaBloomFilter::new(guava.num_hashes(), guava.capacity_bits());
+        bloom_filter.insert_all(&words);
+        assert_eq!(bytes, bloom_filter.to_guava_bytes());
+    }
+}
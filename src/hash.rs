@@ -0,0 +1,523 @@
+use core::hash::{BuildHasher, Hasher};
+
+#[cfg(feature = "xxhash")]
+mod xxhash;
+
+#[cfg(feature = "xxhash")]
+pub use self::xxhash::XxHash;
+
+/// A trait for hashing an arbitrary stream of bytes into a bloom filter.
+pub trait BloomHasher {
+    /// Returns the hashed value of the bytes given some seed.
+    fn hash(&self, seed: u32, bytes: &[u8]) -> u32;
+
+    /// Returns a 64-bit hashed value of the bytes given some seed.
+    ///
+    /// By default, this combines the 32-bit hashes for `seed` and `seed + 1`.
+    /// Hashers with a native 64-bit output should override this.
+    fn hash64(&self, seed: u32, bytes: &[u8]) -> u64 {
+        ((self.hash(seed, bytes) as u64) << 32) | self.hash(seed.wrapping_add(1), bytes) as u64
+    }
+
+    /// Returns a 128-bit hashed value of the bytes given some seed.
+    ///
+    /// Filters derive every probe of an element from the low and high 64 bits
+    /// of this hash. By default, the high half is `hash64` with its 32-bit
+    /// halves swapped, so it carries no more entropy than `hash64`. Hashers
+    /// with a native 128-bit output should override this, so that the halves
+    /// are independent.
+    fn hash128(&self, seed: u32, bytes: &[u8]) -> u128 {
+        let hash = self.hash64(seed, bytes);
+        ((hash.rotate_left(32) as u128) << 64) | hash as u128
+    }
+}
+
+/// A unit struct for the murmur3 hash function.
+///
+/// This uses the 32-bit x86 variant of murmur3. Its 64-bit and 128-bit hashes
+/// are derived from two 32-bit hashes with consecutive seeds, see
+/// `BloomHasher::hash64`.
+#[derive(Clone, Default)]
+pub struct Murmur3;
+
+impl BloomHasher for Murmur3 {
+    fn hash(&self, seed: u32, bytes: &[u8]) -> u32 {
+        murmur3_32(seed, bytes)
+    }
+}
+
+/// A unit struct for the 128-bit x64 variant of the murmur3 hash function.
+///
+/// This provides a native 128-bit hash from a single pass over the bytes,
+/// which is faster than combining two 32-bit hashes. Filters use its two
+/// independent 64-bit halves as the base hashes of every probe, which keeps
+/// the probes of filters with many hash functions well distributed. `hash64`
+/// is the low half of the 128-bit hash.
+#[derive(Clone, Default)]
+pub struct Murmur3_128;
+
+impl BloomHasher for Murmur3_128 {
+    fn hash(&self, seed: u32, bytes: &[u8]) -> u32 {
+        self.hash64(seed, bytes) as u32
+    }
+
+    fn hash64(&self, seed: u32, bytes: &[u8]) -> u64 {
+        murmur3_x64_128(seed, bytes).0
+    }
+
+    fn hash128(&self, seed: u32, bytes: &[u8]) -> u128 {
+        let (low, high) = murmur3_x64_128(seed, bytes);
+        ((high as u128) << 64) | low as u128
+    }
+}
+
+/// A unit struct for the FNV-1a hash function.
+///
+/// FNV-1a processes a single byte per multiplication without any setup or
+/// finalization, which makes it faster than `Murmur3` for short keys. Its
+/// output is less well distributed, so it is best suited to small filters of
+/// short keys where latency matters most.
+///
+/// The seed is mixed in by XORing it into the offset basis. `hash64` uses the
+/// 64-bit variant of FNV-1a directly instead of combining two 32-bit hashes.
+#[derive(Clone, Default)]
+pub struct Fnv1a;
+
+impl BloomHasher for Fnv1a {
+    fn hash(&self, seed: u32, bytes: &[u8]) -> u32 {
+        bytes.iter().fold(0x811c_9dc5 ^ seed, |hash, &byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        })
+    }
+
+    fn hash64(&self, seed: u32, bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325 ^ seed as u64, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+}
+
+/// An adapter implementing `BloomHasher` for any `core::hash::BuildHasher`.
+///
+/// The seed is written into a fresh hasher before the bytes, and the low 32
+/// bits of the result are used for `hash`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bloom_filter_rs as bloom_filter;
+///
+/// use std::collections::hash_map::RandomState;
+/// use bloom_filter::{BloomFilter, StdHasher};
+///
+/// let mut bloom_filter = BloomFilter::optimal(StdHasher::new(RandomState::new()), 100, 0.01);
+///
+/// bloom_filter.insert(b"Hello");
+///
+/// assert!(bloom_filter.contains("Hello"));
+/// ```
+#[derive(Clone, Default)]
+pub struct StdHasher<H> {
+    build_hasher: H,
+}
+
+impl<H: BuildHasher> StdHasher<H> {
+    /// Create a new `StdHasher` building hashers from `build_hasher`.
+    pub fn new(build_hasher: H) -> Self {
+        Self { build_hasher }
+    }
+}
+
+impl<H: BuildHasher> BloomHasher for StdHasher<H> {
+    fn hash(&self, seed: u32, bytes: &[u8]) -> u32 {
+        self.hash64(seed, bytes) as u32
+    }
+
+    fn hash64(&self, seed: u32, bytes: &[u8]) -> u64 {
+        let mut hasher = self.build_hasher.build_hasher();
+        hasher.write_u32(seed);
+        hasher.write(bytes);
+        hasher.finish()
+    }
+}
+
+/// A keyed hasher using SipHash-1-3.
+///
+/// Unlike `Murmur3`, the output of SipHash cannot be predicted without knowing
+/// the key, so an attacker cannot craft keys that collide in the filter. The
+/// seed of each probe is mixed into the key, so every probe is independent.
+///
+/// SipHash is roughly two to three times slower than `Murmur3`, so it is best
+/// reserved for filters built from untrusted input. The key should be
+/// generated randomly and kept secret.
+#[derive(Clone)]
+pub struct SipHash13 {
+    key: (u64, u64),
+}
+
+impl SipHash13 {
+    /// Create a new `SipHash13` from the two halves of a 128-bit key.
+    pub fn new(k0: u64, k1: u64) -> Self {
+        Self { key: (k0, k1) }
+    }
+}
+
+impl BloomHasher for SipHash13 {
+    fn hash(&self, seed: u32, bytes: &[u8]) -> u32 {
+        self.hash64(seed, bytes) as u32
+    }
+
+    fn hash64(&self, seed: u32, bytes: &[u8]) -> u64 {
+        siphash13(self.key.0 ^ seed as u64, self.key.1, bytes)
+    }
+}
+
+/// Compute the 32-bit x86 variant of murmur3 of `bytes` with `seed`.
+fn murmur3_32(seed: u32, bytes: &[u8]) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    let mut h = seed;
+
+    let mut chunks = bytes.chunks_exact(4);
+    for chunk in chunks.by_ref() {
+        let mut word = [0; 4];
+        word.copy_from_slice(chunk);
+        let k = u32::from_le_bytes(word);
+
+        h ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        h = h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        let mut last = [0; 4];
+        last[..remainder.len()].copy_from_slice(remainder);
+        let k = u32::from_le_bytes(last);
+
+        h ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+
+    h ^= bytes.len() as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+/// Compute the 128-bit x64 variant of murmur3 of `bytes` with `seed`, as its
+/// low and high halves.
+pub(crate) fn murmur3_x64_128(seed: u32, bytes: &[u8]) -> (u64, u64) {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+    let mut h1 = seed as u64;
+    let mut h2 = seed as u64;
+
+    let mut chunks = bytes.chunks_exact(16);
+    for chunk in chunks.by_ref() {
+        let (k1, k2) = read_u64_pair(chunk);
+
+        h1 ^= k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+        h1 = h1.rotate_left(27).wrapping_add(h2).wrapping_mul(5).wrapping_add(0x52dc_e729);
+
+        h2 ^= k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+        h2 = h2.rotate_left(31).wrapping_add(h1).wrapping_mul(5).wrapping_add(0x3849_5ab5);
+    }
+
+    let remainder = chunks.remainder();
+    if remainder.len() > 8 {
+        let (_, k2) = read_u64_pair(remainder);
+        h2 ^= k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+    }
+    if !remainder.is_empty() {
+        let (k1, _) = read_u64_pair(remainder);
+        h1 ^= k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+    }
+
+    h1 ^= bytes.len() as u64;
+    h2 ^= bytes.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+
+    (h1, h2)
+}
+
+/// Read up to 16 bytes as two little-endian words, padding with zeros.
+#[inline]
+fn read_u64_pair(bytes: &[u8]) -> (u64, u64) {
+    let mut block = [0; 16];
+    block[..bytes.len()].copy_from_slice(bytes);
+
+    let mut low = [0; 8];
+    let mut high = [0; 8];
+    low.copy_from_slice(&block[..8]);
+    high.copy_from_slice(&block[8..]);
+    (u64::from_le_bytes(low), u64::from_le_bytes(high))
+}
+
+#[inline]
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
+/// Compute the SipHash-1-3 of `bytes` with the key `(k0, k1)`.
+fn siphash13(k0: u64, k1: u64, bytes: &[u8]) -> u64 {
+    let mut v = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in chunks.by_ref() {
+        let mut word = [0; 8];
+        word.copy_from_slice(chunk);
+        let m = u64::from_le_bytes(word);
+
+        v[3] ^= m;
+        sip_round(&mut v);
+        v[0] ^= m;
+    }
+
+    let mut last = [0; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    let b = u64::from_le_bytes(last) | ((bytes.len() as u64) << 56);
+
+    v[3] ^= b;
+    sip_round(&mut v);
+    v[0] ^= b;
+
+    v[2] ^= 0xff;
+    for _ in 0..3 {
+        sip_round(&mut v);
+    }
+
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[inline]
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::RandomState;
+    use std::io::Cursor;
+    use super::*;
+    use bloom::BloomFilter;
+
+    #[test]
+    fn test_std_hasher_consistent() {
+        let hasher = StdHasher::new(RandomState::new());
+
+        assert_eq!(hasher.hash(0, b"hello"), hasher.hash(0, b"hello"));
+        assert_eq!(hasher.hash(7, b"hello"), hasher.hash(7, b"hello"));
+        assert_ne!(hasher.hash(0, b"hello"), hasher.hash(1, b"hello"));
+        assert_ne!(hasher.hash(0, b"hello"), hasher.hash(0, b"world"));
+    }
+
+    #[test]
+    fn test_murmur3_matches_reference() {
+        let bytes: Vec<u8> = (0..64).collect();
+
+        for len in 0..bytes.len() {
+            let bytes = &bytes[..len];
+            let expected = murmur3::murmur3_32(&mut Cursor::new(bytes), 42);
+
+            assert_eq!(murmur3_32(42, bytes), expected);
+        }
+    }
+
+    #[test]
+    fn test_murmur3_128_matches_reference() {
+        let bytes: Vec<u8> = (0..64).collect();
+
+        for len in 0..bytes.len() {
+            let bytes = &bytes[..len];
+            let mut expected = [0; 16];
+            murmur3::murmur3_x64_128(&mut Cursor::new(bytes), 42, &mut expected);
+            let (low, high) = murmur3_x64_128(42, bytes);
+
+            assert_eq!(&expected[..8], &low.to_le_bytes());
+            assert_eq!(&expected[8..], &high.to_le_bytes());
+        }
+
+        assert_eq!(murmur3_x64_128(0, b"hello"), (0xcbd8_a7b3_41bd_9b02, 0x5b1e_906a_48ae_1d19));
+    }
+
+    #[test]
+    fn test_default_hash64() {
+        let hash64 = Murmur3.hash64(3, b"hello");
+
+        assert_eq!(Murmur3.hash(3, b"hello"), (hash64 >> 32) as u32);
+        assert_eq!(Murmur3.hash(4, b"hello"), hash64 as u32);
+    }
+
+    #[test]
+    fn test_murmur3_128() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut bloom_filter = BloomFilter::optimal(Murmur3_128, 1000, 0.01);
+        bloom_filter.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(bloom_filter.contains(word));
+        }
+        assert_eq!(Murmur3_128.hash64(0, b"hello") as u32, Murmur3_128.hash(0, b"hello"));
+        assert_ne!(Murmur3_128.hash64(0, b"hello"), Murmur3_128.hash64(1, b"hello"));
+    }
+
+    #[test]
+    fn test_hash128() {
+        let hash64 = Murmur3.hash64(3, b"hello");
+        assert_eq!(((hash64.rotate_left(32) as u128) << 64) | hash64 as u128, Murmur3.hash128(3, b"hello"));
+
+        let (low, high) = murmur3_x64_128(3, b"hello");
+        assert_eq!(((high as u128) << 64) | low as u128, Murmur3_128.hash128(3, b"hello"));
+        assert_eq!(low, Murmur3_128.hash64(3, b"hello"));
+    }
+
+    /// A hasher splitting a single 32-bit hash into both lanes of its 64-bit
+    /// hash.
+    struct Split32;
+
+    impl BloomHasher for Split32 {
+        fn hash(&self, seed: u32, bytes: &[u8]) -> u32 {
+            murmur3_32(seed, bytes)
+        }
+
+        fn hash64(&self, seed: u32, bytes: &[u8]) -> u64 {
+            let hash = murmur3_32(seed, bytes) as u64;
+            (hash << 32) | hash
+        }
+    }
+
+    fn measured_false_positive_rate<T: BloomHasher>(hasher: T, k: u32) -> f64 {
+        let mut bloom_filter = BloomFilter::new(hasher, k, 200_000);
+        for i in 0..10000_u64 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+        let false_positives = (10000..510000_u64)
+            .filter(|i| bloom_filter.contains(i.to_le_bytes()))
+            .count();
+        false_positives as f64 / 500000_f64
+    }
+
+    #[test]
+    fn test_murmur3_128_false_positive_rate_high_k() {
+        // With a single 32-bit hash the probes of different elements are
+        // correlated, which many hash functions make far more visible.
+        for &k in [14, 20].iter() {
+            let split = measured_false_positive_rate(Split32, k);
+            let murmur3_128 = measured_false_positive_rate(Murmur3_128, k);
+            assert!(murmur3_128 * 5_f64 < split, "k={}: {} vs {}", k, murmur3_128, split);
+        }
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        // Reference values of the unseeded 32-bit and 64-bit FNV-1a.
+        assert_eq!(0x811c_9dc5, Fnv1a.hash(0, b""));
+        assert_eq!(0xe40c_292c, Fnv1a.hash(0, b"a"));
+        assert_eq!(0xaf63_dc4c_8601_ec8c, Fnv1a.hash64(0, b"a"));
+        assert_ne!(Fnv1a.hash64(0, b"hello"), Fnv1a.hash64(1, b"hello"));
+
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut bloom_filter = BloomFilter::optimal(Fnv1a, 1000, 0.01);
+        bloom_filter.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(bloom_filter.contains(word));
+        }
+    }
+
+    #[test]
+    fn test_fnv1a_false_positive_rate() {
+        let mut bloom_filter = BloomFilter::optimal(Fnv1a, 10000, 0.01);
+        for i in 0..10000_u64 {
+            bloom_filter.insert(i.to_le_bytes());
+        }
+        let false_positives = (10000..110000_u64)
+            .filter(|i| bloom_filter.contains(i.to_le_bytes()))
+            .count();
+        // Sequential keys are a weak spot of FNV-1a, so allow some slack over
+        // the target error rate.
+        assert!((false_positives as f64 / 100000_f64) < 0.02);
+    }
+
+    #[test]
+    fn test_siphash13_keys() {
+        let words: Vec<String> = (0..100).map(|i| format!("word{}", i)).collect();
+
+        let mut a = BloomFilter::optimal(SipHash13::new(1, 2), 100, 0.01);
+        let mut b = BloomFilter::optimal(SipHash13::new(3, 4), 100, 0.01);
+        a.insert_all(&words);
+        b.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(a.contains(word));
+            assert!(b.contains(word));
+        }
+        assert!(a.to_bytes() != b.to_bytes());
+    }
+
+    #[test]
+    fn test_siphash13_matches_reference() {
+        // SipHash-1-3 with the key `00 01 .. 0f` of the reference test vectors,
+        // over the inputs `[]`, `[00]`, `[00, 01]` and so on, as produced by
+        // the SipHash-1-3 of Rust's standard library, `core::hash::SipHasher13`.
+        let expected: [u64; 17] = [
+            0xabac_0158_050f_c4dc,
+            0xc9f4_9bf3_7d57_ca93,
+            0x82cb_9b02_4dc7_d44d,
+            0x8bf8_0ab8_e7dd_f7fb,
+            0xcf75_5760_88d3_8328,
+            0xdef9_d52f_4953_3b67,
+            0xc50d_2b50_c59f_22a7,
+            0xd392_7d98_9bb1_1140,
+            0x3690_9511_8d29_9a8e,
+            0x25a4_8eb3_6c06_3de4,
+            0x79de_85ee_92ff_097f,
+            0x70c1_18c1_f94d_c352,
+            0x78a3_84b1_57b4_d9a2,
+            0x306f_760c_1229_ffa7,
+            0x605a_a111_c0f9_5d34,
+            0xd320_d86d_2a51_9956,
+            0xcc4f_dd1a_7d90_8b66,
+        ];
+
+        let hasher = SipHash13::new(0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908);
+        let input: Vec<u8> = (0..16).collect();
+        for (len, &hash) in expected.iter().enumerate() {
+            assert_eq!(hash, hasher.hash64(0, &input[..len]), "input length {}", len);
+        }
+    }
+
+    #[test]
+    fn test_siphash13_seeds() {
+        let hasher = SipHash13::new(1, 2);
+
+        assert_eq!(hasher.hash(0, b"hello"), hasher.hash(0, b"hello"));
+        assert_ne!(hasher.hash(0, b"hello"), hasher.hash(1, b"hello"));
+    }
+}
@@ -0,0 +1,68 @@
+use twox_hash::{XxHash32, XxHash64};
+
+use hash::BloomHasher;
+
+use core::hash::Hasher;
+
+/// A hasher using the xxHash hash function, which is faster than `Murmur3` for
+/// longer keys.
+///
+/// The seed of each probe is XORed into the seed of the `XxHash`, so filters
+/// using different seeds are independent. `hash` uses the 32-bit variant of
+/// xxHash, with both halves of the seed folded together, while `hash64` uses
+/// the native 64-bit variant.
+#[derive(Clone, Default)]
+pub struct XxHash {
+    pub seed: u64,
+}
+
+impl BloomHasher for XxHash {
+    fn hash(&self, seed: u32, bytes: &[u8]) -> u32 {
+        let folded = self.seed as u32 ^ (self.seed >> 32) as u32;
+
+        let mut hasher = XxHash32::with_seed(folded ^ seed);
+        hasher.write(bytes);
+        hasher.finish() as u32
+    }
+
+    fn hash64(&self, seed: u32, bytes: &[u8]) -> u64 {
+        let mut hasher = XxHash64::with_seed(self.seed ^ seed as u64);
+        hasher.write(bytes);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bloom::BloomFilter;
+
+    #[test]
+    fn test_reference_values() {
+        // Reference values of the unseeded 32-bit and 64-bit xxHash.
+        assert_eq!(0x02cc_5d05, XxHash::default().hash(0, b""));
+        assert_eq!(0xef46_db37_51d8_e999, XxHash::default().hash64(0, b""));
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut bloom_filter = BloomFilter::optimal(XxHash { seed: 42 }, 1000, 0.01);
+        bloom_filter.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(bloom_filter.contains(word));
+        }
+    }
+
+    #[test]
+    fn test_seeds() {
+        let a = XxHash { seed: 1 };
+        let b = XxHash { seed: 2 };
+
+        assert_eq!(a.hash64(0, b"hello"), a.hash64(0, b"hello"));
+        assert_ne!(a.hash64(0, b"hello"), a.hash64(1, b"hello"));
+        assert_ne!(a.hash64(0, b"hello"), b.hash64(0, b"hello"));
+        assert_ne!(a.hash(0, b"hello"), b.hash(0, b"hello"));
+    }
+}
@@ -0,0 +1,193 @@
+use bit_vec::BitVec;
+
+use bloom::{checked_len, fastrange};
+use error::BloomError;
+use hash::BloomHasher;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// IndependentBloomFilter
+///
+/// A bloom filter computing each of its `k` bit indices with a separate
+/// hasher, instead of deriving them all from a single hash.
+///
+/// Probe `i` uses the 64-bit hash of hasher `i` with the seed `i`, so the same
+/// hasher type may appear more than once. Mixing hasher types, for example
+/// `Murmur3` and `SipHash13`, hedges against a weakness in either of them.
+///
+/// Every hasher is called through dynamic dispatch and hashes the whole
+/// element, so inserts and queries cost `k` full hashes and virtual calls,
+/// compared to a single hash for `BloomFilter`.
+pub struct IndependentBloomFilter {
+    hashers: Vec<Box<dyn BloomHasher>>,
+    bit_vec: BitVec,
+    insert_count: u64,
+}
+
+impl IndependentBloomFilter {
+    /// Create a new `IndependentBloomFilter` using one hash function from each
+    /// of `hashers`, with a bit array of `array_size` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hashers` is empty, `array_size` is zero or `array_size` does
+    /// not fit in a `usize`. See `try_new` for a non-panicking alternative.
+    pub fn new(hashers: Vec<Box<dyn BloomHasher>>, array_size: u64) -> Self {
+        Self::try_new(hashers, array_size).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a new `IndependentBloomFilter`, returning
+    /// `BloomError::ZeroHashFunctions` if `hashers` is empty and
+    /// `BloomError::ZeroArraySize` if `array_size` is zero, and
+    /// `BloomError::CapacityTooLarge` if it does not fit in a `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bloom_filter_rs as bloom_filter;
+    ///
+    /// use bloom_filter::{BloomHasher, IndependentBloomFilter, Murmur3, SipHash13};
+    ///
+    /// let hashers: Vec<Box<dyn BloomHasher>> = vec![
+    ///     Box::new(Murmur3),
+    ///     Box::new(SipHash13::new(1, 2)),
+    ///     Box::new(Murmur3),
+    /// ];
+    /// let mut bloom_filter = IndependentBloomFilter::try_new(hashers, 1000).unwrap();
+    ///
+    /// bloom_filter.insert("Hello");
+    ///
+    /// assert!(bloom_filter.contains("Hello"));
+    /// ```
+    pub fn try_new(hashers: Vec<Box<dyn BloomHasher>>, array_size: u64) -> Result<Self, BloomError> {
+        if hashers.is_empty() {
+            return Err(BloomError::ZeroHashFunctions);
+        }
+        if array_size == 0 {
+            return Err(BloomError::ZeroArraySize);
+        }
+
+        Ok(Self {
+            hashers,
+            bit_vec: BitVec::from_elem(checked_len(array_size)?, false),
+            insert_count: 0,
+        })
+    }
+
+    /// Insert a slice of bytes into the `IndependentBloomFilter`.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        for i in 0..self.hashers.len() {
+            let index = self.index(i, bytes.as_ref());
+            self.bit_vec.set(index, true);
+        }
+        self.insert_count += 1;
+    }
+
+    /// Insert a slice of slices of bytes into the `IndependentBloomFilter`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the `IndependentBloomFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        (0..self.hashers.len()).all(|i| self.bit_vec[self.index(i, bytes.as_ref())])
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.hashers.len() as u32
+    }
+
+    /// Returns the size of the underlying bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.bit_vec.len() as u64
+    }
+
+    /// Returns the number of times `insert` has been called.
+    pub fn len(&self) -> u64 {
+        self.insert_count
+    }
+
+    /// Returns `true` if nothing has been inserted into the `IndependentBloomFilter`.
+    pub fn is_empty(&self) -> bool {
+        self.insert_count == 0
+    }
+
+    /// Returns the bit index of `bytes` for the `i`-th hasher.
+    #[inline]
+    fn index(&self, i: usize, bytes: &[u8]) -> usize {
+        fastrange(self.hashers[i].hash64(i as u32, bytes), self.bit_vec.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::{Murmur3, SipHash13};
+
+    fn hashers() -> Vec<Box<dyn BloomHasher>> {
+        vec![
+            Box::new(Murmur3),
+            Box::new(SipHash13::new(1, 2)),
+            Box::new(Murmur3),
+            Box::new(SipHash13::new(3, 4)),
+            Box::new(Murmur3),
+            Box::new(SipHash13::new(5, 6)),
+            Box::new(Murmur3),
+        ]
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+
+        let mut filter = IndependentBloomFilter::new(hashers(), 9586);
+        filter.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(filter.contains(word));
+        }
+        assert_eq!(7, filter.num_hashes());
+        assert_eq!(1000, filter.len());
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let mut filter = IndependentBloomFilter::new(hashers(), 9586);
+        for i in 0..1000_u32 {
+            filter.insert(i.to_le_bytes());
+        }
+
+        let false_positives = (1000..101000_u32)
+            .filter(|i| filter.contains(i.to_le_bytes()))
+            .count();
+        assert!((false_positives as f64 / 100000_f64) < 0.015);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            Some(BloomError::ZeroHashFunctions),
+            IndependentBloomFilter::try_new(Vec::new(), 100).err()
+        );
+        assert_eq!(
+            Some(BloomError::ZeroArraySize),
+            IndependentBloomFilter::try_new(hashers(), 0).err()
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn test_capacity_too_large_32_bit() {
+        assert_eq!(
+            Some(BloomError::CapacityTooLarge),
+            IndependentBloomFilter::try_new(hashers(), u32::MAX as u64 + 1).err()
+        );
+    }
+}
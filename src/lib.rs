@@ -1,7 +1,75 @@
 //! Implementation of a BloomFilter
+//!
+//! The crate supports `no_std` targets with an allocator by disabling the
+//! default `std` feature and enabling `libm` for the floating point math.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("either the `std` or the `libm` feature must be enabled");
+
+#[macro_use]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate core;
 
 extern crate bit_vec;
+#[cfg(all(feature = "libm", not(feature = "std")))]
+extern crate libm;
+#[cfg(feature = "compression")]
+extern crate flate2;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "xxhash")]
+extern crate twox_hash;
+
+#[cfg(all(test, feature = "serde"))]
+extern crate bincode;
+#[cfg(test)]
 extern crate murmur3;
 
+mod atomic;
+mod blocked;
 mod bloom;
-pub use bloom::*;
\ No newline at end of file
+mod builder;
+mod chained;
+mod counting;
+mod cuckoo;
+mod error;
+mod fixed;
+mod frozen;
+mod guava;
+mod hash;
+mod independent;
+mod math;
+mod partitioned;
+#[cfg(feature = "std")]
+mod recording;
+mod scalable;
+mod stable;
+mod view;
+mod windowed;
+pub use atomic::*;
+pub use blocked::*;
+pub use bloom::*;
+pub use builder::*;
+pub use chained::*;
+pub use counting::*;
+pub use cuckoo::*;
+pub use error::*;
+pub use fixed::*;
+pub use frozen::*;
+pub use guava::*;
+pub use hash::*;
+pub use independent::*;
+pub use partitioned::*;
+#[cfg(feature = "std")]
+pub use recording::*;
+pub use scalable::*;
+pub use stable::*;
+pub use view::*;
+pub use windowed::*;
\ No newline at end of file
@@ -0,0 +1,64 @@
+//! Floating point functions used for sizing filters.
+//!
+//! These are inherent methods of `f64` with `std`, and are provided by `libm`
+//! otherwise.
+
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn ln(x: f64) -> f64 {
+    x.ln()
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn ln(x: f64) -> f64 {
+    libm::log(x)
+}
+
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn exp(x: f64) -> f64 {
+    x.exp()
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn exp(x: f64) -> f64 {
+    libm::exp(x)
+}
+
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn powf(x: f64, y: f64) -> f64 {
+    x.powf(y)
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn powf(x: f64, y: f64) -> f64 {
+    libm::pow(x, y)
+}
+
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn ceil(x: f64) -> f64 {
+    x.ceil()
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn ceil(x: f64) -> f64 {
+    libm::ceil(x)
+}
+
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn round(x: f64) -> f64 {
+    x.round()
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn round(x: f64) -> f64 {
+    libm::round(x)
+}
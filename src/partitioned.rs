@@ -0,0 +1,191 @@
+use bit_vec::BitVec;
+
+use bloom::{checked_len, optimal_parameters, Probes};
+use error::BloomError;
+use hash::BloomHasher;
+
+/// PartitionedBloomFilter
+///
+/// A bloom filter whose bit array is split into `k` equal slices, with the
+/// `i`-th hash function of an element setting a bit only within the `i`-th
+/// slice.
+///
+/// Every element sets exactly one bit per slice, so the slices fill at the same
+/// rate independently of each other, and no two hash functions of an element
+/// can collide on the same bit.
+#[derive(Clone)]
+pub struct PartitionedBloomFilter<T> {
+    hasher: T,
+    k: u32,
+    slice_len: usize,
+    bit_vec: BitVec,
+    insert_count: u64,
+}
+
+impl<T: BloomHasher> PartitionedBloomFilter<T> {
+    /// Create a new `PartitionedBloomFilter` given a `hasher`,
+    /// the number of hash functions to use,
+    /// and the size of the underlying bit array.
+    ///
+    /// `k` is clamped to a minimum of 1, and `array_size` is rounded up to a
+    /// multiple of `k` so that every slice has the same length of at least 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `array_size` does not fit in a `usize`, as on 32-bit targets.
+    pub fn new(hasher: T, k: u32, array_size: u64) -> Self {
+        let k = k.max(1);
+        let slice_len = array_size.div_ceil(k as u64).max(1);
+        let len = checked_len(slice_len.saturating_mul(k as u64));
+        let len = len.unwrap_or_else(|e| panic!("{}", e));
+        Self {
+            hasher,
+            k,
+            slice_len: slice_len as usize,
+            bit_vec: BitVec::from_elem(len, false),
+            insert_count: 0,
+        }
+    }
+
+    /// Create a `PartitionedBloomFilter` by computing its optimal parameters.
+    ///
+    /// The array size and number of hash functions are computed exactly as for
+    /// `BloomFilter::optimal`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    /// See `try_optimal` for a non-panicking alternative.
+    pub fn optimal(hasher: T, max_elements: u64, error_rate: f64) -> Self {
+        Self::try_optimal(hasher, max_elements, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a `PartitionedBloomFilter` by computing its optimal parameters,
+    /// returning an error if the parameters are invalid.
+    pub fn try_optimal(hasher: T, max_elements: u64, error_rate: f64) -> Result<Self, BloomError> {
+        let (k, m) = optimal_parameters(max_elements, error_rate)?;
+        Ok(Self::new(hasher, k, m))
+    }
+
+    /// Insert a slice of bytes into the `PartitionedBloomFilter`.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        let slice_len = self.slice_len;
+        for (i, index) in self.probes(bytes.as_ref()).enumerate() {
+            self.bit_vec.set(i * slice_len + index, true);
+        }
+        self.insert_count += 1;
+    }
+
+    /// Insert a slice of slices of bytes into the `PartitionedBloomFilter`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the `PartitionedBloomFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.probes(bytes.as_ref())
+            .enumerate()
+            .all(|(i, index)| self.bit_vec[i * self.slice_len + index])
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the size of the underlying bit array.
+    pub fn capacity_bits(&self) -> u64 {
+        self.bit_vec.len() as u64
+    }
+
+    /// Returns the number of bits in each of the `k` slices.
+    pub fn slice_bits(&self) -> u64 {
+        self.slice_len as u64
+    }
+
+    /// Returns the number of times `insert` has been called.
+    pub fn len(&self) -> u64 {
+        self.insert_count
+    }
+
+    /// Returns `true` if nothing has been inserted into the `PartitionedBloomFilter`.
+    pub fn is_empty(&self) -> bool {
+        self.insert_count == 0
+    }
+
+    /// Returns the `k` indices of `bytes`, each within a single slice.
+    fn probes(&self, bytes: &[u8]) -> Probes {
+        Probes::new(&self.hasher, 0, self.k, self.slice_len, bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_insert_as_ref() {
+        let mut filter = PartitionedBloomFilter::optimal(Murmur3, 100, 0.01);
+        let word = String::from("apple");
+        filter.insert(&word);
+        filter.insert("banana");
+        filter.insert(vec![1_u8, 2, 3]);
+        filter.insert(word);
+
+        assert!(filter.contains("apple"));
+        assert!(filter.contains("banana"));
+        assert!(filter.contains([1_u8, 2, 3]));
+        assert_eq!(4, filter.len());
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let words: Vec<String> = (0..10000).map(|i| format!("word{}", i)).collect();
+
+        let mut filter = PartitionedBloomFilter::optimal(Murmur3, 10000, 0.01);
+        filter.insert_all(&words);
+
+        for word in words.iter() {
+            assert!(filter.contains(word));
+        }
+        assert_eq!(10000, filter.len());
+    }
+
+    #[test]
+    fn test_slices_rounded_up() {
+        let filter = PartitionedBloomFilter::new(Murmur3, 7, 100);
+        assert_eq!(15, filter.slice_bits());
+        assert_eq!(105, filter.capacity_bits());
+    }
+
+    #[test]
+    fn test_slices_fill_evenly() {
+        let mut filter = PartitionedBloomFilter::optimal(Murmur3, 10000, 0.01);
+        for i in 0..10000_u32 {
+            filter.insert(i.to_le_bytes());
+        }
+
+        let slice_len = filter.slice_len;
+        let fills: Vec<f64> = (0..filter.k as usize)
+            .map(|i| {
+                let set = (i * slice_len..(i + 1) * slice_len)
+                    .filter(|&index| filter.bit_vec[index])
+                    .count();
+                set as f64 / slice_len as f64
+            })
+            .collect();
+
+        // Each slice receives one bit per element, so every slice is expected to
+        // be filled to 1 - e^(-n / slice_len), roughly one half.
+        let expected = 1.0 - (-10000.0 / slice_len as f64).exp();
+        for fill in fills {
+            assert!((fill - expected).abs() < 0.02, "{} vs {}", fill, expected);
+        }
+    }
+}
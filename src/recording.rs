@@ -0,0 +1,255 @@
+use bloom::{optimal_hash_functions, optimal_parameters, BloomFilter};
+use error::BloomError;
+use hash::BloomHasher;
+
+use std::collections::HashSet;
+
+/// RecordingBloomFilter
+///
+/// A debugging aid wrapping a `BloomFilter` that also records an exact copy of
+/// every inserted element, so that the elements can be listed with `iter`.
+///
+/// A bloom filter only stores bits derived from its elements and can never
+/// list them, this type works around that by keeping every element in a
+/// `HashSet` on the side. This costs far more memory than the filter itself,
+/// so it is meant for tests and debugging, not for production use.
+///
+/// Queries are still answered by the filter, so `contains` returns the same
+/// false positives as an unwrapped `BloomFilter` would.
+pub struct RecordingBloomFilter<T> {
+    bloom_filter: BloomFilter<T>,
+    elements: HashSet<Vec<u8>>,
+    /// Whether the wrapped filter had bits set before recording started.
+    unrecorded: bool,
+}
+
+impl<T: BloomHasher> RecordingBloomFilter<T> {
+    /// Create a new `RecordingBloomFilter` given a `hasher`,
+    /// the number of hash functions to use,
+    /// and the size of the underlying bit array.
+    ///
+    /// Both `k` and `array_size` are clamped to a minimum of 1.
+    pub fn new(hasher: T, k: u32, array_size: u64) -> Self {
+        Self::from_bloom_filter(BloomFilter::new(hasher, k, array_size))
+    }
+
+    /// Create a `RecordingBloomFilter` by computing its optimal parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `max_elements` is zero.
+    /// See `try_optimal` for a non-panicking alternative.
+    pub fn optimal(hasher: T, max_elements: u64, error_rate: f64) -> Self {
+        Self::try_optimal(hasher, max_elements, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a `RecordingBloomFilter` by computing its optimal parameters,
+    /// returning an error if the parameters are invalid.
+    pub fn try_optimal(hasher: T, max_elements: u64, error_rate: f64) -> Result<Self, BloomError> {
+        BloomFilter::try_optimal(hasher, max_elements, error_rate).map(Self::from_bloom_filter)
+    }
+
+    /// Wrap `bloom_filter` in a `RecordingBloomFilter`.
+    ///
+    /// Only elements inserted after wrapping are recorded, so `bloom_filter`
+    /// should usually be empty. If it is not, `iter` does not list its
+    /// elements and `grow` cannot be used.
+    pub fn from_bloom_filter(bloom_filter: BloomFilter<T>) -> Self {
+        let unrecorded = bloom_filter.set_bits().next().is_some();
+        Self {
+            bloom_filter,
+            elements: HashSet::new(),
+            unrecorded,
+        }
+    }
+
+    /// Insert a slice of bytes into the `RecordingBloomFilter`.
+    ///
+    /// Returns `true` if at least one bit of the filter was flipped, as
+    /// `BloomFilter::insert` does.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) -> bool {
+        self.elements.insert(bytes.as_ref().to_vec());
+        self.bloom_filter.insert(bytes)
+    }
+
+    /// Insert a slice of slices of bytes into the `RecordingBloomFilter`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the underlying filter.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative, the recorded elements are not consulted.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.bloom_filter.contains(bytes)
+    }
+
+    /// Returns an iterator over the distinct elements inserted into the
+    /// `RecordingBloomFilter`, in an arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.elements.iter().map(|element| &element[..])
+    }
+
+    /// Returns the number of times `insert` has been called.
+    pub fn len(&self) -> u64 {
+        self.bloom_filter.len()
+    }
+
+    /// Returns `true` if nothing has been inserted into the `RecordingBloomFilter`.
+    pub fn is_empty(&self) -> bool {
+        self.bloom_filter.is_empty()
+    }
+
+    /// Returns the underlying `BloomFilter`.
+    pub fn bloom_filter(&self) -> &BloomFilter<T> {
+        &self.bloom_filter
+    }
+
+    /// Unwrap the underlying `BloomFilter`, discarding the recorded elements.
+    pub fn into_bloom_filter(self) -> BloomFilter<T> {
+        self.bloom_filter
+    }
+}
+
+impl<T: BloomHasher + Clone> RecordingBloomFilter<T> {
+    /// Replace the underlying filter with one sized by its optimal parameters
+    /// for `new_error_rate` and twice the larger of its current capacity at
+    /// `new_error_rate` and the number of recorded elements, and insert every
+    /// recorded element into it. The new bit array is never smaller than the
+    /// old one.
+    ///
+    /// This recovers a filter that was filled past its capacity. The new filter
+    /// keeps the hasher and seed offset of the old one, and `len` afterwards
+    /// counts each distinct element once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_error_rate` is not within `(0, 1)`, or if the filter
+    /// passed to `from_bloom_filter` was not empty, since its elements were
+    /// never recorded and would be lost. See `try_grow` for a non-panicking
+    /// alternative.
+    pub fn grow(&mut self, new_error_rate: f64) {
+        self.try_grow(new_error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Grow the underlying filter as `grow` does, leaving the filter unchanged
+    /// and returning an error if `new_error_rate` is invalid, or
+    /// `BloomError::UnrecordedElements` if the filter holds elements that
+    /// were not recorded.
+    pub fn try_grow(&mut self, new_error_rate: f64) -> Result<(), BloomError> {
+        if self.unrecorded {
+            return Err(BloomError::UnrecordedElements);
+        }
+
+        let capacity = self.bloom_filter.capacity_for_error(new_error_rate);
+        let max_elements = capacity.max(self.elements.len() as u64).saturating_mul(2).max(1);
+        let (_, m) = optimal_parameters(max_elements, new_error_rate)?;
+        let m = m.max(self.bloom_filter.capacity_bits());
+        let k = optimal_hash_functions(m, max_elements);
+
+        let hasher = self.bloom_filter.hasher().clone();
+        let mut bloom_filter = BloomFilter::try_new(hasher, k, m)?
+            .with_target_error_rate(new_error_rate)
+            .with_seed_offset(self.bloom_filter.seed_offset());
+        for element in self.elements.iter() {
+            bloom_filter.insert(element);
+        }
+
+        self.bloom_filter = bloom_filter;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_iter() {
+        let words = ["apple", "banana", "cherry", "banana"];
+        let mut recording = RecordingBloomFilter::optimal(Murmur3, 100, 0.01);
+        recording.insert_all(&words);
+        assert_eq!(4, recording.len());
+
+        let mut elements: Vec<&[u8]> = recording.iter().collect();
+        elements.sort();
+        assert_eq!(vec![&b"apple"[..], b"banana", b"cherry"], elements);
+    }
+
+    #[test]
+    fn test_contains_matches_bloom_filter() {
+        let words: Vec<String> = (0..1000).map(|i| format!("word{}", i)).collect();
+        let mut recording = RecordingBloomFilter::optimal(Murmur3, 100, 0.1);
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.1);
+        recording.insert_all(&words[..100]);
+        bloom_filter.insert_all(&words[..100]);
+
+        for word in words.iter() {
+            assert_eq!(bloom_filter.contains(word), recording.contains(word));
+        }
+        assert_eq!(&bloom_filter, recording.bloom_filter());
+    }
+
+    #[test]
+    fn test_grow() {
+        let words: Vec<String> = (0..10400).map(|i| format!("word{}", i)).collect();
+        let measured_false_positive_rate = |recording: &RecordingBloomFilter<Murmur3>| {
+            let non_members = words[400..].iter();
+            non_members.filter(|word| recording.contains(word)).count() as f64 / 10000_f64
+        };
+
+        // Four times the capacity of the filter.
+        let mut recording = RecordingBloomFilter::optimal(Murmur3, 100, 0.1);
+        recording.insert_all(&words[..400]);
+        let before = measured_false_positive_rate(&recording);
+        assert!(before > 0.5);
+
+        recording.grow(0.01);
+        let after = measured_false_positive_rate(&recording);
+        assert!(after < 0.01, "{}", after);
+        assert!(words[..400].iter().all(|word| recording.contains(word)));
+        assert_eq!(400, recording.len());
+        assert_eq!(400, recording.iter().count());
+
+        assert_eq!(Err(BloomError::InvalidErrorRate(1.5)), recording.try_grow(1.5));
+        assert!(words[..400].iter().all(|word| recording.contains(word)));
+    }
+
+    #[test]
+    fn test_grow_never_shrinks() {
+        let mut recording = RecordingBloomFilter::optimal(Murmur3, 10000, 0.01);
+        recording.insert_all(&["apple", "banana", "cherry"]);
+
+        for &error_rate in [0.01, 0.1, 0.5, 0.0001].iter() {
+            let before = recording.bloom_filter().capacity_bits();
+            recording.grow(error_rate);
+            assert!(recording.bloom_filter().capacity_bits() >= before);
+            assert_eq!(error_rate, recording.bloom_filter().target_error_rate());
+        }
+
+        // Growing at the same error rate doubles the capacity.
+        let mut recording = RecordingBloomFilter::optimal(Murmur3, 10000, 0.01);
+        recording.grow(0.01);
+        assert!(recording.bloom_filter().capacity_for_error(0.01) >= 19900);
+    }
+
+    #[test]
+    fn test_grow_unrecorded() {
+        let mut bloom_filter = BloomFilter::optimal(Murmur3, 100, 0.01);
+        bloom_filter.insert("apple");
+
+        let mut recording = RecordingBloomFilter::from_bloom_filter(bloom_filter);
+        recording.insert("banana");
+        assert_eq!(Err(BloomError::UnrecordedElements), recording.try_grow(0.01));
+        assert!(recording.contains("apple"));
+        assert!(recording.contains("banana"));
+
+        let mut recording = RecordingBloomFilter::optimal(Murmur3, 100, 0.01);
+        recording.insert("banana");
+        assert_eq!(Ok(()), recording.try_grow(0.01));
+    }
+}
@@ -0,0 +1,194 @@
+use bloom::{check_error_rate, BloomFilter};
+use error::BloomError;
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+
+/// The factor by which the capacity of each new filter grows.
+const GROWTH_FACTOR: u64 = 2;
+
+/// The factor by which the error rate of each new filter is tightened.
+const TIGHTENING_RATIO: f64 = 0.8;
+
+/// ScalableBloomFilter
+///
+/// A bloom filter that grows as elements are inserted, as described by
+/// Almeida et al. in "Scalable Bloom Filters".
+///
+/// Once the active filter reaches its capacity, a new filter is added with
+/// twice the capacity and a tighter error rate. The error rates of the
+/// filters form a geometric series, so the overall false positive rate stays
+/// below the requested error rate no matter how many elements are inserted.
+pub struct ScalableBloomFilter<T> {
+    hasher: T,
+    filters: Vec<BloomFilter<T>>,
+    capacity: u64,
+    error_rate: f64,
+}
+
+impl<T: BloomHasher + Clone> ScalableBloomFilter<T> {
+    /// Create a new `ScalableBloomFilter` whose first filter holds
+    /// `initial_capacity` elements, and whose overall false positive rate
+    /// stays below `error_rate`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `initial_capacity` is zero.
+    /// See `try_new` for a non-panicking alternative.
+    pub fn new(hasher: T, initial_capacity: u64, error_rate: f64) -> Self {
+        Self::try_new(hasher, initial_capacity, error_rate).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a new `ScalableBloomFilter`, returning an error if the parameters
+    /// are invalid.
+    pub fn try_new(hasher: T, initial_capacity: u64, error_rate: f64) -> Result<Self, BloomError> {
+        check_error_rate(error_rate)?;
+
+        // The first filter gets the largest share of the error rate, such that
+        // the sum over all filters converges to `error_rate`.
+        let error_rate = error_rate * (1_f64 - TIGHTENING_RATIO);
+        let first = BloomFilter::try_optimal(hasher.clone(), initial_capacity, error_rate)?;
+
+        Ok(Self {
+            hasher,
+            filters: vec![first],
+            capacity: initial_capacity,
+            error_rate,
+        })
+    }
+
+    /// Insert a slice of bytes into the `ScalableBloomFilter`.
+    ///
+    /// Only the most recently added filter is modified.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        if self.active().len() >= self.capacity {
+            self.capacity = self.capacity.saturating_mul(GROWTH_FACTOR);
+            self.error_rate *= TIGHTENING_RATIO;
+
+            let filter = BloomFilter::optimal(self.hasher.clone(), self.capacity, self.error_rate);
+            self.filters.push(filter);
+        }
+
+        self.active_mut().insert(bytes);
+    }
+
+    /// Insert a slice of slices of bytes into the `ScalableBloomFilter`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in any of the filters.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.filters.iter().any(|filter| filter.contains(bytes.as_ref()))
+    }
+
+    /// Returns the number of times `insert` has been called.
+    pub fn len(&self) -> u64 {
+        self.filters.iter().map(|filter| filter.len()).sum()
+    }
+
+    /// Returns `true` if nothing has been inserted into the `ScalableBloomFilter`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of filters that have been allocated.
+    pub fn num_filters(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Calculate the expected false positive rate given the current state of
+    /// all the filters.
+    pub fn false_positive_rate(&self) -> f64 {
+        1_f64
+            - self
+                .filters
+                .iter()
+                .map(|filter| 1_f64 - filter.false_positive_rate())
+                .product::<f64>()
+    }
+
+    fn active(&self) -> &BloomFilter<T> {
+        self.filters.last().unwrap()
+    }
+
+    fn active_mut(&mut self) -> &mut BloomFilter<T> {
+        self.filters.last_mut().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_insert_as_ref() {
+        let mut filter = ScalableBloomFilter::new(Murmur3, 100, 0.01);
+        let word = String::from("apple");
+        filter.insert(&word);
+        filter.insert("banana");
+        filter.insert(vec![1_u8, 2, 3]);
+        filter.insert(word);
+
+        assert!(filter.contains("apple"));
+        assert!(filter.contains("banana"));
+        assert!(filter.contains([1_u8, 2, 3]));
+        assert_eq!(4, filter.len());
+    }
+
+    #[test]
+    fn test_grows() {
+        let mut filter = ScalableBloomFilter::new(Murmur3, 100, 0.01);
+        assert_eq!(1, filter.num_filters());
+        assert!(filter.is_empty());
+
+        for i in 0..100_u32 {
+            filter.insert(i.to_le_bytes());
+        }
+        assert_eq!(1, filter.num_filters());
+
+        // 100 + 200 + 400 < 1000 <= 100 + 200 + 400 + 800
+        for i in 100..1000_u32 {
+            filter.insert(i.to_le_bytes());
+        }
+        assert_eq!(4, filter.num_filters());
+        assert_eq!(1000, filter.len());
+    }
+
+    #[test]
+    fn test_false_positive_rate_bounded() {
+        let mut filter = ScalableBloomFilter::new(Murmur3, 100, 0.01);
+        for i in 0..10000_u32 {
+            filter.insert(i.to_le_bytes());
+        }
+
+        for i in 0..10000_u32 {
+            assert!(filter.contains(i.to_le_bytes()));
+        }
+
+        assert!(filter.false_positive_rate() < 0.01);
+
+        let false_positives = (10000..110000_u32)
+            .filter(|i| filter.contains(i.to_le_bytes()))
+            .count();
+        assert!((false_positives as f64 / 100000_f64) < 0.01);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert_eq!(
+            Some(BloomError::ZeroElements),
+            ScalableBloomFilter::try_new(Murmur3, 0, 0.01).err()
+        );
+        assert_eq!(
+            Some(BloomError::InvalidErrorRate(2_f64)),
+            ScalableBloomFilter::try_new(Murmur3, 100, 2_f64).err()
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use bloom::{checked_len, fastrange, splitmix64, Probes};
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+
+/// StableBloomFilter
+///
+/// A bloom filter for deduplicating unbounded streams in fixed memory, which
+/// makes room for new elements by gradually forgetting old ones.
+///
+/// Every cell is a small counter. Each insert first decrements `decrements`
+/// randomly chosen cells, then sets the `k` cells of the element to
+/// `max_value`. The fraction of non-zero cells converges to a stable point
+/// instead of growing until every query is a false positive, as it would in a
+/// `BloomFilter` receiving an endless stream.
+///
+/// Unlike the other filters in this crate, a `StableBloomFilter` **can return
+/// false negatives**: an element is forgotten once any of its cells has been
+/// decremented to zero, which becomes likely after roughly
+/// `max_value * num_cells / decrements` further inserts. The most recently
+/// inserted element is always found.
+///
+/// The cells are chosen by a PRNG with a fixed default seed, so a filter
+/// behaves identically across runs unless `with_seed` is used.
+#[derive(Clone)]
+pub struct StableBloomFilter<T> {
+    hasher: T,
+    k: u32,
+    max_value: u8,
+    decrements: u32,
+    cells: Vec<u8>,
+    rng_state: u64,
+    insert_count: u64,
+}
+
+impl<T: BloomHasher> StableBloomFilter<T> {
+    /// Create a new `StableBloomFilter` given a `hasher`, the number of hash
+    /// functions to use, the number of cells, the value cells are set to on
+    /// insert, and the number of cells decremented on every insert.
+    ///
+    /// `k`, `num_cells` and `max_value` are clamped to a minimum of 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_cells` does not fit in a `usize`, as on 32-bit targets.
+    pub fn new(hasher: T, k: u32, num_cells: u64, max_value: u8, decrements: u32) -> Self {
+        Self {
+            hasher,
+            k: k.max(1),
+            max_value: max_value.max(1),
+            decrements,
+            cells: vec![0; checked_len(num_cells.max(1)).unwrap_or_else(|e| panic!("{}", e))],
+            rng_state: 0,
+            insert_count: 0,
+        }
+    }
+
+    /// Seed the PRNG choosing the cells decremented on every insert.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng_state = seed;
+        self
+    }
+
+    /// Insert a slice of bytes into the `StableBloomFilter`.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        for _ in 0..self.decrements {
+            let index = fastrange(splitmix64(&mut self.rng_state), self.cells.len());
+            self.cells[index] = self.cells[index].saturating_sub(1);
+        }
+
+        for index in self.probes(bytes.as_ref()) {
+            self.cells[index] = self.max_value;
+        }
+        self.insert_count += 1;
+    }
+
+    /// Insert a slice of slices of bytes into the `StableBloomFilter`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the `StableBloomFilter`.
+    ///
+    /// This is a probabilistic function that may return a false positive, and
+    /// may also return a false negative for an element that has been forgotten.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.probes(bytes.as_ref()).all(|index| self.cells[index] > 0)
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the number of cells.
+    pub fn num_cells(&self) -> u64 {
+        self.cells.len() as u64
+    }
+
+    /// Returns the fraction of cells that are not zero.
+    pub fn fill_ratio(&self) -> f64 {
+        let set = self.cells.iter().filter(|&&cell| cell > 0).count();
+        set as f64 / self.cells.len() as f64
+    }
+
+    /// Returns the number of times `insert` has been called.
+    pub fn len(&self) -> u64 {
+        self.insert_count
+    }
+
+    /// Returns `true` if nothing has been inserted into the `StableBloomFilter`.
+    pub fn is_empty(&self) -> bool {
+        self.insert_count == 0
+    }
+
+    /// Returns the `k` cell indices for `bytes`.
+    fn probes(&self, bytes: &[u8]) -> Probes {
+        Probes::new(&self.hasher, 0, self.k, self.cells.len(), bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_bounded_fill() {
+        let mut filter = StableBloomFilter::new(Murmur3, 3, 10000, 3, 10);
+
+        let mut fill_ratios = Vec::new();
+        for i in 0..200000_u32 {
+            filter.insert(i.to_le_bytes());
+            if (i + 1) % 50000 == 0 {
+                fill_ratios.push(filter.fill_ratio());
+            }
+        }
+
+        // The fill ratio settles instead of approaching 1.
+        for fill_ratio in fill_ratios.iter() {
+            assert!(*fill_ratio < 0.7, "fill ratio of {}", fill_ratio);
+            assert!((fill_ratio - fill_ratios[0]).abs() < 0.02);
+        }
+        assert_eq!(200000, filter.len());
+    }
+
+    #[test]
+    fn test_forgets_old_elements() {
+        let mut filter = StableBloomFilter::new(Murmur3, 3, 10000, 3, 10);
+        filter.insert(b"first");
+        assert!(filter.contains("first"));
+
+        for i in 0..100000_u32 {
+            filter.insert(i.to_le_bytes());
+            assert!(filter.contains(i.to_le_bytes()));
+        }
+
+        // Old elements produce false negatives, recent ones are still found.
+        // About 55% of the cells are set at the stable point, so roughly 16% of
+        // the forgotten elements are still reported as false positives.
+        assert!(!filter.contains("first"));
+        let forgotten = (0..1000_u32).filter(|i| !filter.contains(i.to_le_bytes())).count();
+        assert!(forgotten > 750, "{} of 1000 forgotten", forgotten);
+        let remembered = (99000..100000_u32).filter(|i| filter.contains(i.to_le_bytes())).count();
+        assert!(remembered > 900, "{} of 1000 remembered", remembered);
+    }
+
+    #[test]
+    fn test_with_seed() {
+        let mut a = StableBloomFilter::new(Murmur3, 3, 1000, 3, 10).with_seed(1);
+        let mut b = StableBloomFilter::new(Murmur3, 3, 1000, 3, 10).with_seed(2);
+        for i in 0..1000_u32 {
+            a.insert(i.to_le_bytes());
+            b.insert(i.to_le_bytes());
+        }
+        assert_ne!(a.cells, b.cells);
+    }
+}
@@ -0,0 +1,182 @@
+use bloom::Probes;
+use hash::BloomHasher;
+
+/// BloomFilterView
+///
+/// A bloom filter operating on a range of bits of an externally owned byte
+/// buffer, created by `BloomFilter::in_buffer`.
+///
+/// This allows packing many small filters densely into a single allocation,
+/// each view borrowing its own range of the buffer. Bits are numbered from the
+/// most significant bit of each byte, as in `BitVec::to_bytes`, so a view at
+/// offset zero uses the same layout as the bit array of a `BloomFilter` with
+/// the same parameters.
+///
+/// The buffer only holds the bits, so a view does not track how many elements
+/// were inserted, and no statistics depending on it are available.
+pub struct BloomFilterView<'a, T> {
+    hasher: T,
+    k: u32,
+    bit_offset: usize,
+    len: usize,
+    buffer: &'a mut [u8],
+}
+
+impl<'a, T: BloomHasher> BloomFilterView<'a, T> {
+    /// Create a `BloomFilterView` over the `bit_len` bits of `buffer` starting
+    /// at `bit_offset`, see `BloomFilter::in_buffer`.
+    ///
+    /// `k` is clamped to a minimum of 1. The bits in the range are used as
+    /// they are, so a range that was not zeroed starts out with elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_len` is zero or the range does not fit in `buffer`.
+    pub fn new(hasher: T, k: u32, bit_offset: usize, bit_len: usize, buffer: &'a mut [u8]) -> Self {
+        assert!(bit_len > 0, "bit length must be non-zero");
+        assert!(
+            bit_offset
+                .checked_add(bit_len)
+                .is_some_and(|end| end <= buffer.len() * 8),
+            "bit range {}..{} does not fit in a buffer of {} bytes",
+            bit_offset,
+            bit_offset.saturating_add(bit_len),
+            buffer.len()
+        );
+
+        Self {
+            hasher,
+            k: k.max(1),
+            bit_offset,
+            len: bit_len,
+            buffer,
+        }
+    }
+
+    /// Insert a slice of bytes into the `BloomFilterView`.
+    ///
+    /// Returns `true` if at least one bit was flipped, which guarantees the
+    /// element was not present before.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) -> bool {
+        let mut new = false;
+        for index in self.probes(bytes.as_ref()) {
+            let (byte, mask) = self.locate(index);
+            new |= self.buffer[byte] & mask == 0;
+            self.buffer[byte] |= mask;
+        }
+        new
+    }
+
+    /// Insert a slice of slices of bytes into the `BloomFilterView`.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        for item in slice {
+            self.insert(item);
+        }
+    }
+
+    /// Check whether a slice of bytes exists in the `BloomFilterView`.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.probes(bytes.as_ref()).all(|index| {
+            let (byte, mask) = self.locate(index);
+            self.buffer[byte] & mask != 0
+        })
+    }
+
+    /// Returns the number of hash functions used for each element.
+    pub fn num_hashes(&self) -> u32 {
+        self.k
+    }
+
+    /// Returns the number of bits of the buffer used by the `BloomFilterView`.
+    pub fn capacity_bits(&self) -> u64 {
+        self.len as u64
+    }
+
+    /// Returns the offset of the first bit of the `BloomFilterView` in the
+    /// buffer.
+    pub fn bit_offset(&self) -> usize {
+        self.bit_offset
+    }
+
+    /// Remove every element from the `BloomFilterView`, clearing only its own
+    /// range of the buffer.
+    pub fn clear(&mut self) {
+        for index in 0..self.len {
+            let (byte, mask) = self.locate(index);
+            self.buffer[byte] &= !mask;
+        }
+    }
+
+    /// Returns the `k` bit indices for `bytes`, relative to the range.
+    fn probes(&self, bytes: &[u8]) -> Probes {
+        Probes::new(&self.hasher, 0, self.k, self.len, bytes)
+    }
+
+    /// Returns the byte and bit mask of the bit at `index` of the range.
+    fn locate(&self, index: usize) -> (usize, u8) {
+        let bit = self.bit_offset + index;
+        (bit / 8, 0x80 >> (bit % 8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bloom::BloomFilter;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_packed_filters_do_not_interfere() {
+        // Two filters sharing a byte at an unaligned boundary.
+        let mut buffer = vec![0_u8; 250];
+        let (first, second) = (1003, 997);
+        let words: Vec<String> = (0..200).map(|i| format!("word{}", i)).collect();
+
+        {
+            let mut view = BloomFilter::in_buffer(Murmur3, 7, 0, first, &mut buffer);
+            view.insert_all(&words[..100]);
+        }
+        let before = buffer.clone();
+        {
+            let mut view = BloomFilter::in_buffer(Murmur3, 7, first, second, &mut buffer);
+            view.insert_all(&words[100..]);
+        }
+
+        // The bits of the first filter were not changed by the second.
+        for bit in 0..first {
+            let mask = 0x80 >> (bit % 8);
+            assert_eq!(before[bit / 8] & mask, buffer[bit / 8] & mask);
+        }
+
+        let view = BloomFilter::in_buffer(Murmur3, 7, 0, first, &mut buffer);
+        assert!(words[..100].iter().all(|word| view.contains(word)));
+        let view = BloomFilter::in_buffer(Murmur3, 7, first, second, &mut buffer);
+        assert!(words[100..].iter().all(|word| view.contains(word)));
+
+        let mut view = BloomFilter::in_buffer(Murmur3, 7, 0, first, &mut buffer);
+        view.clear();
+        let view = BloomFilter::in_buffer(Murmur3, 7, first, second, &mut buffer);
+        assert!(words[100..].iter().all(|word| view.contains(word)));
+    }
+
+    #[test]
+    fn test_same_bits_as_bloom_filter() {
+        let mut buffer = vec![0_u8; 125];
+        let mut bloom_filter = BloomFilter::new(Murmur3, 7, 1000);
+        let mut view = BloomFilter::in_buffer(Murmur3, 7, 0, 1000, &mut buffer);
+        for i in 0..100_u32 {
+            assert_eq!(bloom_filter.insert(i.to_le_bytes()), view.insert(i.to_le_bytes()));
+        }
+
+        assert_eq!(bloom_filter.bit_vec().to_bytes(), buffer);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit")]
+    fn test_range_out_of_bounds() {
+        let mut buffer = vec![0_u8; 8];
+        BloomFilter::in_buffer(Murmur3, 7, 60, 5, &mut buffer);
+    }
+}
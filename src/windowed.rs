@@ -0,0 +1,161 @@
+use bloom::{check_error_rate, BloomFilter};
+use error::BloomError;
+use hash::BloomHasher;
+
+use alloc::vec::Vec;
+
+/// WindowedBloomFilter
+///
+/// A bloom filter that forgets old elements, made of several generations of
+/// filters of which the oldest is cleared on every call to `rotate`.
+///
+/// Elements are inserted into the current generation, and are found by
+/// `contains` until `rotate` has been called once per generation. Calling
+/// `rotate` on a timer therefore approximates a time to live for every element
+/// without storing any timestamps: with `n` generations rotated every `t`
+/// seconds, an element is remembered for between `(n - 1) * t` and `n * t`
+/// seconds.
+#[derive(Clone)]
+pub struct WindowedBloomFilter<T> {
+    generations: Vec<BloomFilter<T>>,
+    current: usize,
+}
+
+impl<T: BloomHasher + Clone> WindowedBloomFilter<T> {
+    /// Create a new `WindowedBloomFilter` of `generations` filters, each
+    /// holding `capacity` elements, whose overall false positive rate stays
+    /// below `error_rate`.
+    ///
+    /// `contains` checks every generation, so each generation is given an
+    /// error rate of `error_rate / generations`. The number of generations is
+    /// clamped to a minimum of 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `(0, 1)` or `capacity` is zero.
+    /// See `try_new` for a non-panicking alternative.
+    pub fn new(hasher: T, generations: usize, capacity: u64, error_rate: f64) -> Self {
+        Self::try_new(hasher, generations, capacity, error_rate)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a new `WindowedBloomFilter`, returning an error if the parameters
+    /// are invalid.
+    pub fn try_new(
+        hasher: T,
+        generations: usize,
+        capacity: u64,
+        error_rate: f64,
+    ) -> Result<Self, BloomError> {
+        check_error_rate(error_rate)?;
+
+        let generations = generations.max(1);
+        let filter = BloomFilter::try_optimal(hasher, capacity, error_rate / generations as f64)?;
+
+        Ok(Self {
+            generations: vec![filter; generations],
+            current: 0,
+        })
+    }
+
+    /// Insert a slice of bytes into the current generation.
+    pub fn insert<B: AsRef<[u8]>>(&mut self, bytes: B) {
+        self.generations[self.current].insert(bytes);
+    }
+
+    /// Insert a slice of slices of bytes into the current generation.
+    pub fn insert_all<B: AsRef<[u8]>>(&mut self, slice: &[B]) {
+        self.generations[self.current].insert_all(slice);
+    }
+
+    /// Check whether a slice of bytes exists in any live generation.
+    ///
+    /// This is a probabilistic function that may return a false positive but will
+    /// never return a false negative for elements inserted since the last
+    /// `generations` rotations.
+    pub fn contains<B: AsRef<[u8]>>(&self, bytes: B) -> bool {
+        self.generations.iter().any(|filter| filter.contains(bytes.as_ref()))
+    }
+
+    /// Start a new generation, forgetting every element of the oldest one.
+    ///
+    /// The oldest generation is cleared and becomes the current generation,
+    /// so no memory is allocated.
+    pub fn rotate(&mut self) {
+        self.current = (self.current + 1) % self.generations.len();
+        self.generations[self.current].clear();
+    }
+
+    /// Returns the number of generations.
+    pub fn num_generations(&self) -> usize {
+        self.generations.len()
+    }
+
+    /// Returns the number of times `insert` has been called for the live
+    /// generations.
+    pub fn len(&self) -> u64 {
+        self.generations.iter().map(|filter| filter.len()).sum()
+    }
+
+    /// Returns `true` if nothing has been inserted into the live generations.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hash::Murmur3;
+
+    #[test]
+    fn test_expires_after_window() {
+        let mut filter = WindowedBloomFilter::new(Murmur3, 3, 100, 0.01);
+        filter.insert("apple");
+
+        filter.rotate();
+        filter.insert("banana");
+        filter.rotate();
+        assert!(filter.contains("apple"));
+        assert!(filter.contains("banana"));
+        assert_eq!(2, filter.len());
+
+        filter.rotate();
+        assert!(!filter.contains("apple"));
+        assert!(filter.contains("banana"));
+
+        filter.rotate();
+        assert!(!filter.contains("banana"));
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn test_single_generation() {
+        let mut filter = WindowedBloomFilter::new(Murmur3, 0, 100, 0.01);
+        assert_eq!(1, filter.num_generations());
+
+        filter.insert_all(&["apple", "banana"]);
+        assert!(filter.contains("apple"));
+
+        filter.rotate();
+        assert!(!filter.contains("apple"));
+    }
+
+    #[test]
+    fn test_false_positive_rate_bounded() {
+        let mut filter = WindowedBloomFilter::new(Murmur3, 4, 2500, 0.01);
+        for i in 0..10000_u32 {
+            if i % 2500 == 0 && i > 0 {
+                filter.rotate();
+            }
+            filter.insert(i.to_le_bytes());
+        }
+
+        let false_positives = (10000..110000_u32)
+            .filter(|i| filter.contains(i.to_le_bytes()))
+            .count();
+        // Every generation is at capacity, so the expected rate is just under
+        // the target, allow for sampling noise.
+        assert!((false_positives as f64 / 100000_f64) < 0.0125);
+    }
+}